@@ -0,0 +1,68 @@
+// Cross-checks our legal move generation against shakmaty over the positions
+// of random games, stopping at the first disagreement.
+//
+//     cargo run --release --no-default-features --example differential_movegen -- 1000
+
+use chess_game::notation::move_to_uci;
+use chess_game::simulation::simulate_random_game;
+use chess_game::Game;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Position};
+
+fn main() {
+    let games = std::env::args()
+        .nth(1)
+        .and_then(|games| games.parse::<u64>().ok())
+        .unwrap_or(100);
+
+    let mut positions = 0;
+    for seed in 0..games {
+        let record = simulate_random_game(seed);
+        let mut game = Game::new();
+        let mut reference = Chess::default();
+        for ply in 0..=record.moves.len() {
+            let mut ours = game
+                .get_board()
+                .get_all_available_moves(game.get_turn())
+                .iter()
+                .map(move_to_uci)
+                .collect::<Vec<String>>();
+            let mut theirs = reference
+                .legal_moves()
+                .iter()
+                .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+                .collect::<Vec<String>>();
+            ours.sort();
+            theirs.sort();
+            positions += 1;
+
+            if ours != theirs {
+                println!("seed {}, ply {}: move generation differs", seed, ply);
+                println!("moves: {}", game.get_san_moves().join(" "));
+                println!("{}", game.get_board());
+                println!(
+                    "only ours: {:?}",
+                    ours.iter()
+                        .filter(|mv| !theirs.contains(mv))
+                        .collect::<Vec<_>>()
+                );
+                println!(
+                    "only shakmaty: {:?}",
+                    theirs
+                        .iter()
+                        .filter(|mv| !ours.contains(mv))
+                        .collect::<Vec<_>>()
+                );
+                std::process::exit(1);
+            }
+
+            if let Some(mv) = record.moves.get(ply) {
+                let uci = move_to_uci(mv).parse::<UciMove>().unwrap();
+                let reference_move = uci.to_move(&reference).unwrap();
+                reference.play_unchecked(reference_move);
+                game.make_move(mv).unwrap();
+            }
+        }
+    }
+    println!("{} games, {} positions, no differences", games, positions);
+}
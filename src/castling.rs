@@ -0,0 +1,105 @@
+use super::{Color, Position};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CastlingSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastlingSide {
+    // Castling is encoded as the king moving two squares towards the rook
+    pub fn from_king_move(from: Position, to: Position) -> Option<Self> {
+        if from.get_row() != to.get_row() || from.get_column() != 4 {
+            return None;
+        }
+        match to.get_column() {
+            6 => Some(CastlingSide::KingSide),
+            2 => Some(CastlingSide::QueenSide),
+            _ => None,
+        }
+    }
+
+    pub fn get_rook_column(&self) -> usize {
+        match self {
+            CastlingSide::KingSide => 7,
+            CastlingSide::QueenSide => 0,
+        }
+    }
+
+    // Columns the king and the rook end up on
+    pub fn get_king_destination_column(&self) -> usize {
+        match self {
+            CastlingSide::KingSide => 6,
+            CastlingSide::QueenSide => 2,
+        }
+    }
+
+    pub fn get_rook_destination_column(&self) -> usize {
+        match self {
+            CastlingSide::KingSide => 5,
+            CastlingSide::QueenSide => 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    white_king_side: bool,
+    white_queen_side: bool,
+    black_king_side: bool,
+    black_queen_side: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_king_side: true,
+            white_queen_side: true,
+            black_king_side: true,
+            black_queen_side: true,
+        }
+    }
+
+    fn get_right_mut(&mut self, color: Color, side: CastlingSide) -> &mut bool {
+        match (color, side) {
+            (Color::White, CastlingSide::KingSide) => &mut self.white_king_side,
+            (Color::White, CastlingSide::QueenSide) => &mut self.white_queen_side,
+            (Color::Black, CastlingSide::KingSide) => &mut self.black_king_side,
+            (Color::Black, CastlingSide::QueenSide) => &mut self.black_queen_side,
+        }
+    }
+
+    pub fn can_castle(&self, color: Color, side: CastlingSide) -> bool {
+        match (color, side) {
+            (Color::White, CastlingSide::KingSide) => self.white_king_side,
+            (Color::White, CastlingSide::QueenSide) => self.white_queen_side,
+            (Color::Black, CastlingSide::KingSide) => self.black_king_side,
+            (Color::Black, CastlingSide::QueenSide) => self.black_queen_side,
+        }
+    }
+
+    pub fn set(&mut self, color: Color, side: CastlingSide, allowed: bool) {
+        *self.get_right_mut(color, side) = allowed;
+    }
+
+    pub fn remove_all(&mut self, color: Color) {
+        self.set(color, CastlingSide::KingSide, false);
+        self.set(color, CastlingSide::QueenSide, false);
+    }
+
+    // A rook leaving or being captured on its starting square loses its right
+    pub fn remove_for_square(&mut self, pos: Position) {
+        let color = match pos.get_row() {
+            0 => Color::White,
+            7 => Color::Black,
+            _ => return,
+        };
+        for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+            if pos.get_column() == side.get_rook_column() {
+                self.set(color, side, false);
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use super::{
+    notation, Bishop, Board, CastlingSide, ChessError, ChessPiece, ChessPieceType, Knight,
+    Position, Queen, Rook,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PromotionPiece {
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+}
+
+impl PromotionPiece {
+    pub const ALL: [PromotionPiece; 4] = [
+        PromotionPiece::Queen,
+        PromotionPiece::Rook,
+        PromotionPiece::Bishop,
+        PromotionPiece::Knight,
+    ];
+
+    pub fn to_chess_piece_type(&self) -> ChessPieceType {
+        match self {
+            PromotionPiece::Queen => ChessPieceType::Queen(Queen),
+            PromotionPiece::Rook => ChessPieceType::Rook(Rook),
+            PromotionPiece::Bishop => ChessPieceType::Bishop(Bishop),
+            PromotionPiece::Knight => ChessPieceType::Knight(Knight),
+        }
+    }
+}
+
+// A move is identified by its squares and promotion piece. The remaining
+// fields describe it and are only filled in for moves generated by a Board.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    from: Position,
+    to: Position,
+    promotion: Option<PromotionPiece>,
+    piece: Option<ChessPiece>,
+    captured: Option<ChessPiece>,
+    castling: Option<CastlingSide>,
+    en_passant: bool,
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        self.from == other.from && self.to == other.to && self.promotion == other.promotion
+    }
+}
+
+impl Eq for Move {}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+            piece: None,
+            captured: None,
+            castling: None,
+            en_passant: false,
+        }
+    }
+
+    pub fn with_promotion(from: Position, to: Position, promotion: PromotionPiece) -> Self {
+        Self {
+            promotion: Some(promotion),
+            ..Self::new(from, to)
+        }
+    }
+
+    pub(crate) fn with_details(
+        self,
+        piece: ChessPiece,
+        captured: Option<ChessPiece>,
+        castling: Option<CastlingSide>,
+        en_passant: bool,
+    ) -> Self {
+        Self {
+            piece: Some(piece),
+            captured,
+            castling,
+            en_passant,
+            ..self
+        }
+    }
+
+    pub fn get_from(&self) -> Position {
+        self.from
+    }
+
+    pub fn get_to(&self) -> Position {
+        self.to
+    }
+
+    pub fn get_promotion(&self) -> Option<PromotionPiece> {
+        self.promotion
+    }
+
+    // None for moves that weren't generated by a Board
+    pub fn get_piece(&self) -> Option<ChessPiece> {
+        self.piece
+    }
+
+    pub fn get_captured(&self) -> Option<ChessPiece> {
+        self.captured
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    pub fn get_castling(&self) -> Option<CastlingSide> {
+        self.castling
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.en_passant
+    }
+
+    // The legal move on the board, with its details filled in
+    pub fn from_uci(uci: &str, board: &Board) -> Result<Self, ChessError> {
+        let mv: Move = uci.parse()?;
+        let piece = board
+            .get_piece(mv.from)
+            .as_ref()
+            .ok_or(ChessError::NoPieceAtSquare(mv.from))?;
+        notation::find_legal_move(board, piece.color, &mv)
+    }
+
+    // e.g. "e2e4", "e7e8q"
+    pub fn to_uci(&self) -> String {
+        notation::move_to_uci(self)
+    }
+}
+
+// Formats as UCI
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
+// Parses UCI, without a board the move details are left empty
+impl FromStr for Move {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        notation::move_from_uci(s)
+    }
+}
@@ -0,0 +1,256 @@
+use super::{fen, notation, Board, ChessError, Color, Game, Move, Result};
+use std::fmt;
+
+// An opcode with its operands, e.g. `bm Nf3 Nc3;` or `id "WAC.001";`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+// A position without move counters, followed by any number of operations
+#[derive(Clone)]
+pub struct EpdRecord {
+    pub board: Board,
+    pub turn: Color,
+    pub operations: Vec<EpdOperation>,
+}
+
+// Opcodes whose operand is free text, written quoted
+fn is_string_opcode(opcode: &str) -> bool {
+    matches!(
+        opcode,
+        "id" | "c0" | "c1" | "c2" | "c3" | "c4" | "c5" | "c6" | "c7" | "c8" | "c9"
+    )
+}
+
+// Splits the operations part at semicolons outside of quoted strings
+fn parse_operations(text: &str) -> Result<Vec<EpdOperation>> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                if tokens.is_empty() {
+                    return Err(ChessError::ParseError("empty EPD operation".to_string()));
+                }
+                operations.push(EpdOperation {
+                    opcode: tokens.remove(0),
+                    operands: std::mem::take(&mut tokens),
+                });
+            }
+            '"' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => token.push(c),
+                        None => {
+                            return Err(ChessError::ParseError(
+                                "unterminated string in EPD operation".to_string(),
+                            ))
+                        }
+                    }
+                }
+                tokens.push(token);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    if !tokens.is_empty() {
+        return Err(ChessError::ParseError(format!(
+            "EPD operation {:?} is missing its semicolon",
+            tokens[0]
+        )));
+    }
+    Ok(operations)
+}
+
+impl EpdRecord {
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut rest = line.trim();
+        let mut fields = Vec::new();
+        for _ in 0..4 {
+            let (field, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return Err(ChessError::ParseError(format!(
+                    "expected 4 position fields in {:?}",
+                    line
+                )));
+            }
+            fields.push(field);
+            rest = remainder.trim_start();
+        }
+        let position = fen::parse_fen(&fields.join(" "))?;
+        Ok(EpdRecord {
+            board: position.board,
+            turn: position.turn,
+            operations: parse_operations(rest)?,
+        })
+    }
+
+    pub fn get_operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+            .map(|operation| operation.operands.as_slice())
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        self.get_operation("id")?.first().map(String::as_str)
+    }
+
+    fn get_san_moves(&self, opcode: &str) -> Result<Vec<Move>> {
+        self.get_operation(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| notation::move_from_san(&self.board, self.turn, san))
+            .collect()
+    }
+
+    // The `bm` moves, what a solver should play
+    pub fn get_best_moves(&self) -> Result<Vec<Move>> {
+        self.get_san_moves("bm")
+    }
+
+    // The `am` moves, what a solver should avoid
+    pub fn get_avoid_moves(&self) -> Result<Vec<Move>> {
+        self.get_san_moves("am")
+    }
+
+    // Whether a move solves the record, None when it has neither bm nor am
+    pub fn is_solved_by(&self, mv: &Move) -> Result<Option<bool>> {
+        if self.get_operation("bm").is_some() {
+            Ok(Some(self.get_best_moves()?.contains(mv)))
+        } else if self.get_operation("am").is_some() {
+            Ok(Some(!self.get_avoid_moves()?.contains(mv)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // The move counters come from the hmvc and fmvn operations when present
+    pub fn to_game(&self) -> Result<Game> {
+        let counter = |opcode: &str, default: &str| {
+            self.get_operation(opcode)
+                .and_then(|operands| operands.first().cloned())
+                .unwrap_or(default.to_string())
+        };
+        Game::from_fen(&format!(
+            "{} {} {}",
+            self.position_fields(),
+            counter("hmvc", "0"),
+            counter("fmvn", "1")
+        ))
+    }
+
+    fn position_fields(&self) -> String {
+        fen::to_fen(&self.board, self.turn, 0, 1)
+            .split(' ')
+            .take(4)
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for EpdRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.position_fields())?;
+        for operation in &self.operations {
+            write!(f, " {}", operation.opcode)?;
+            for operand in &operation.operands {
+                let quoted = is_string_opcode(&operation.opcode)
+                    || operand.is_empty()
+                    || operand.contains(|c: char| c.is_whitespace() || c == ';');
+                if quoted {
+                    write!(f, " \"{}\"", operand)?;
+                } else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+// One record per line, blank lines are skipped
+pub fn parse_epd(text: &str) -> Result<Vec<EpdRecord>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(EpdRecord::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAC_001: &str =
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+
+    #[test]
+    fn records_round_trip() {
+        let record = EpdRecord::parse(WAC_001).unwrap();
+        assert_eq!(record.turn, Color::White);
+        assert_eq!(record.get_id(), Some("WAC.001"));
+        assert_eq!(record.to_string(), WAC_001);
+
+        let best = record.get_best_moves().unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].to_uci(), "g3g6");
+        assert_eq!(record.is_solved_by(&best[0]), Ok(Some(true)));
+        let other = notation::move_from_san(&record.board, record.turn, "Qh3").unwrap();
+        assert_eq!(record.is_solved_by(&other), Ok(Some(false)));
+    }
+
+    #[test]
+    fn operations_are_split_outside_strings() {
+        let records = parse_epd(
+            "4k3/8/8/8/8/8/4P3/4K3 w - - am e3; c0 \"a; b\"; hmvc 7; fmvn 40;\n\n\
+             4k3/8/8/8/8/8/4P3/4K3 b - - noop;\n",
+        )
+        .unwrap();
+        assert_eq!(records.len(), 2);
+        let record = &records[0];
+        assert_eq!(record.get_operation("c0"), Some(&["a; b".to_string()][..]));
+        assert_eq!(
+            record.to_string(),
+            "4k3/8/8/8/8/8/4P3/4K3 w - - am e3; c0 \"a; b\"; hmvc 7; fmvn 40;"
+        );
+
+        let avoided = record.get_avoid_moves().unwrap();
+        assert_eq!(record.is_solved_by(&avoided[0]), Ok(Some(false)));
+        assert_eq!(
+            record.to_game().unwrap().to_fen(),
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 7 40"
+        );
+        assert_eq!(records[1].get_operation("noop"), Some(&[][..]));
+        assert_eq!(records[1].is_solved_by(&avoided[0]), Ok(None));
+    }
+
+    #[test]
+    fn broken_records_are_rejected() {
+        for line in [
+            "4k3/8/8/8/8/8/4P3/4K3 w -",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - bm e3",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - id \"open;",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - ;",
+            "4k3/8/8/8/8/8/4P3/9 w - - bm e3;",
+        ] {
+            assert!(EpdRecord::parse(line).is_err(), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use super::{
+    Bishop, Board, CastlingSide, ChessError, ChessPiece, ChessPieceType, Color, King, Knight, Pawn,
+    Position, Queen, Result, Rook,
+};
+
+// Everything a FEN string describes
+#[derive(Clone)]
+pub struct FenPosition {
+    pub board: Board,
+    pub turn: Color,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+fn invalid_position(message: &str) -> ChessError {
+    ChessError::InvalidPosition(message.to_string())
+}
+
+fn piece_to_letter(piece: &ChessPiece) -> char {
+    let letter = match piece.chess_piece {
+        ChessPieceType::Pawn(_) => 'p',
+        ChessPieceType::Knight(_) => 'n',
+        ChessPieceType::Bishop(_) => 'b',
+        ChessPieceType::Rook(_) => 'r',
+        ChessPieceType::Queen(_) => 'q',
+        ChessPieceType::King(_) => 'k',
+    };
+    match piece.color {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+    }
+}
+
+fn piece_from_letter(letter: char) -> Option<ChessPiece> {
+    let chess_piece = match letter.to_ascii_lowercase() {
+        'p' => ChessPieceType::Pawn(Pawn),
+        'n' => ChessPieceType::Knight(Knight),
+        'b' => ChessPieceType::Bishop(Bishop),
+        'r' => ChessPieceType::Rook(Rook),
+        'q' => ChessPieceType::Queen(Queen),
+        'k' => ChessPieceType::King(King),
+        _ => return None,
+    };
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some(ChessPiece::new(chess_piece, color))
+}
+
+// Ranks from the 8th down to the 1st, files from a to h within each
+fn parse_placement(placement: &str) -> Result<Board> {
+    let ranks = placement.split('/').collect::<Vec<&str>>();
+    if ranks.len() != 8 {
+        return Err(ChessError::ParseError(format!(
+            "expected 8 ranks in {:?}",
+            placement
+        )));
+    }
+
+    let mut board = Board::new();
+    for (i, rank) in ranks.iter().enumerate() {
+        let row = 7 - i;
+        let mut column = 0;
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                column += skip as usize;
+            } else if let Some(piece) = piece_from_letter(c) {
+                if column < 8 {
+                    board.set_square(row, column, Some(piece));
+                }
+                column += 1;
+            } else {
+                return Err(ChessError::ParseError(format!(
+                    "invalid character {:?} in rank {}",
+                    c,
+                    row + 1
+                )));
+            }
+            if column > 8 {
+                break;
+            }
+        }
+        if column != 8 {
+            return Err(ChessError::ParseError(format!(
+                "rank {} doesn't have 8 squares",
+                row + 1
+            )));
+        }
+    }
+    Ok(board)
+}
+
+fn parse_castling(board: &mut Board, castling: &str) -> Result<()> {
+    if castling == "-" {
+        return Ok(());
+    }
+    for c in castling.chars() {
+        let (color, side) = match c {
+            'K' => (Color::White, CastlingSide::KingSide),
+            'Q' => (Color::White, CastlingSide::QueenSide),
+            'k' => (Color::Black, CastlingSide::KingSide),
+            'q' => (Color::Black, CastlingSide::QueenSide),
+            _ => {
+                return Err(ChessError::ParseError(format!(
+                    "invalid castling rights {:?}",
+                    castling
+                )))
+            }
+        };
+        if board.castling_rights.can_castle(color, side) {
+            return Err(ChessError::ParseError(format!(
+                "castling right {:?} listed twice",
+                c
+            )));
+        }
+
+        let row = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let king = board.squares[row][4];
+        let rook = board.squares[row][side.get_rook_column()];
+        if !king
+            .is_some_and(|p| p.color == color && matches!(p.chess_piece, ChessPieceType::King(_)))
+            || !rook.is_some_and(|p| {
+                p.color == color && matches!(p.chess_piece, ChessPieceType::Rook(_))
+            })
+        {
+            return Err(invalid_position(&format!(
+                "castling right {:?} without the king and rook on their squares",
+                c
+            )));
+        }
+        board.castling_rights.set(color, side, true);
+    }
+    Ok(())
+}
+
+// The square skipped by the pawn that just made a double push
+fn parse_en_passant(board: &mut Board, turn: Color, en_passant: &str) -> Result<()> {
+    if en_passant == "-" {
+        return Ok(());
+    }
+    let pos: Position = en_passant.parse()?;
+    let (row, pawn_row, origin_row) = match turn {
+        Color::White => (5, 4, 6),
+        Color::Black => (2, 3, 1),
+    };
+    let pawn = board.squares[pawn_row][pos.get_column()];
+    if pos.get_row() != row
+        || board.squares[row][pos.get_column()].is_some()
+        || board.squares[origin_row][pos.get_column()].is_some()
+        || !pawn.is_some_and(|p| {
+            p.color == turn.switch() && matches!(p.chess_piece, ChessPieceType::Pawn(_))
+        })
+    {
+        return Err(invalid_position(&format!(
+            "no pawn can have just skipped {}",
+            en_passant
+        )));
+    }
+    board.en_passant = Some(pos);
+    Ok(())
+}
+
+// What has to hold whichever side is to move
+fn validate_pieces(board: &Board) -> Result<()> {
+    for color in [Color::White, Color::Black] {
+        let kings = board
+            .squares
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|p| p.color == color && matches!(p.chess_piece, ChessPieceType::King(_)))
+            .count();
+        if kings != 1 {
+            return Err(ChessError::InvalidPosition(format!(
+                "{:?} has {} kings",
+                color, kings
+            )));
+        }
+    }
+    let pawn_on_last_row = [0, 7].into_iter().any(|row| {
+        board.squares[row]
+            .iter()
+            .flatten()
+            .any(|p| matches!(p.chess_piece, ChessPieceType::Pawn(_)))
+    });
+    if pawn_on_last_row {
+        return Err(invalid_position("pawn on the first or last rank"));
+    }
+    Ok(())
+}
+
+fn validate(board: &Board, turn: Color) -> Result<()> {
+    validate_pieces(board)?;
+    if board.is_king_in_check(turn.switch())?.is_some() {
+        return Err(invalid_position("the side not to move is in check"));
+    }
+    Ok(())
+}
+
+// Accepts the 4 field form without the move counters as well
+pub fn parse_fen(fen: &str) -> Result<FenPosition> {
+    let fields = fen.split_whitespace().collect::<Vec<&str>>();
+    if !matches!(fields.len(), 4 | 6) {
+        return Err(ChessError::ParseError(format!(
+            "expected 6 fields in {:?}",
+            fen
+        )));
+    }
+
+    let mut board = parse_placement(fields[0])?;
+    let turn = match fields[1] {
+        "w" => Color::White,
+        "b" => Color::Black,
+        other => {
+            return Err(ChessError::ParseError(format!(
+                "invalid side to move {:?}",
+                other
+            )))
+        }
+    };
+    validate(&board, turn)?;
+    parse_castling(&mut board, fields[2])?;
+    parse_en_passant(&mut board, turn, fields[3])?;
+
+    let (halfmove_clock, fullmove_number) = match fields[..] {
+        [_, _, _, _, halfmove, fullmove] => (
+            halfmove.parse::<u32>().map_err(|_| {
+                ChessError::ParseError(format!("invalid halfmove clock {:?}", halfmove))
+            })?,
+            fullmove
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n >= 1)
+                .ok_or_else(|| {
+                    ChessError::ParseError(format!("invalid fullmove number {:?}", fullmove))
+                })?,
+        ),
+        _ => (0, 1),
+    };
+
+    Ok(FenPosition {
+        board,
+        turn,
+        halfmove_clock,
+        fullmove_number,
+    })
+}
+
+pub fn to_fen(board: &Board, turn: Color, halfmove_clock: u32, fullmove_number: u32) -> String {
+    let placement = board
+        .squares
+        .iter()
+        .rev()
+        .map(|rank| {
+            let mut text = String::new();
+            let mut empty = 0;
+            for square in rank {
+                match square {
+                    Some(piece) => {
+                        if empty > 0 {
+                            text.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        text.push(piece_to_letter(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                text.push_str(&empty.to_string());
+            }
+            text
+        })
+        .collect::<Vec<String>>()
+        .join("/");
+
+    let mut castling = String::new();
+    for (color, side, letter) in [
+        (Color::White, CastlingSide::KingSide, 'K'),
+        (Color::White, CastlingSide::QueenSide, 'Q'),
+        (Color::Black, CastlingSide::KingSide, 'k'),
+        (Color::Black, CastlingSide::QueenSide, 'q'),
+    ] {
+        if board.castling_rights.can_castle(color, side) {
+            castling.push(letter);
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+
+    format!(
+        "{} {} {} {} {} {}",
+        placement,
+        if turn == Color::White { "w" } else { "b" },
+        castling,
+        board
+            .en_passant
+            .map_or("-".to_string(), |pos| pos.to_string()),
+        halfmove_clock,
+        fullmove_number
+    )
+}
+
+// A board on its own is stored as the placement, castling and en passant
+// fields of a FEN and held to the same rules when read back. The en passant
+// square tells which side is to move, without one either side may be.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let fen = to_fen(self, Color::White, 0, 1);
+        let fields = fen.split_whitespace().collect::<Vec<&str>>();
+        serializer.collect_str(&format!("{} {} {}", fields[0], fields[2], fields[3]))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let parse = || -> Result<Board> {
+            let [placement, castling, en_passant] =
+                text.split_whitespace().collect::<Vec<&str>>()[..]
+            else {
+                return Err(ChessError::ParseError(format!(
+                    "expected 3 fields in {:?}",
+                    text
+                )));
+            };
+            let mut board = parse_placement(placement)?;
+            validate_pieces(&board)?;
+            parse_castling(&mut board, castling)?;
+            let turn = match en_passant.parse::<Position>() {
+                Ok(pos) if pos.get_row() == 2 => Color::Black,
+                _ => Color::White,
+            };
+            parse_en_passant(&mut board, turn, en_passant)?;
+            Ok(board)
+        };
+        parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(fen: &str) -> String {
+        let position = parse_fen(fen).unwrap();
+        to_fen(
+            &position.board,
+            position.turn,
+            position.halfmove_clock,
+            position.fullmove_number,
+        )
+    }
+
+    #[test]
+    fn round_trips() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+            "8/8/4k3/8/8/4K3/8/8 b - - 37 80",
+        ] {
+            assert_eq!(round_trip(fen), fen);
+        }
+    }
+
+    #[test]
+    fn counters_are_optional() {
+        let position = parse_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+        assert_eq!(position.halfmove_clock, 0);
+        assert_eq!(position.fullmove_number, 1);
+    }
+
+    #[test]
+    fn start_position_matches_new_game() {
+        let position =
+            parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert!(position.board == Board::new_game());
+        assert_eq!(position.turn, Color::White);
+    }
+
+    #[test]
+    fn malformed_fields() {
+        for fen in [
+            "",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0",
+            "4k3/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w X - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            "r3k3/8/8/8/8/8/8/4K3 w qq - 0 1",
+        ] {
+            assert!(
+                matches!(parse_fen(fen), Err(ChessError::ParseError(_))),
+                "{:?}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_positions() {
+        for fen in [
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+            "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/p3K3 b - - 0 1",
+            "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w K - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - e6 0 1",
+        ] {
+            assert!(
+                matches!(parse_fen(fen), Err(ChessError::InvalidPosition(_))),
+                "{:?}",
+                fen
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn boards_are_checked_when_deserialized() {
+        let board = parse_fen("rnbqkbnr/pp2pppp/8/2ppP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3")
+            .unwrap()
+            .board;
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(
+            json,
+            "\"rnbqkbnr/pp2pppp/8/2ppP3/8/8/PPPP1PPP/RNBQKBNR KQkq d6\""
+        );
+        assert!(serde_json::from_str::<Board>(&json).unwrap() == board);
+        for json in [
+            "\"8/8/8/8/8/8/8/4K3 - -\"",
+            "\"4k3/8/8/8/8/8/8/4K3 K -\"",
+            "\"4k3\"",
+        ] {
+            assert!(serde_json::from_str::<Board>(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,809 @@
+use super::{
+    fen, notation,
+    pgn::{self, PgnExportOptions, PgnGame, PgnMode},
+    Board, CastlingRights, ChessError, ChessPieceType, Color, Move, Position, PromotionPiece,
+};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawReason {
+    // Claimed by a player, the game goes on until then
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    // Drawn without a claim
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    InsufficientMaterial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameStatus {
+    Ongoing,
+    // The side to move is in check but has a way out
+    Check,
+    Checkmate { winner: Color },
+    Stalemate,
+    Draw { reason: DrawReason },
+}
+
+// Board state a move overwrites and that can't be derived from the move
+#[derive(Debug, Clone, Copy)]
+struct UndoState {
+    castling_rights: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+}
+
+#[derive(Clone)]
+pub struct Game {
+    // Position the game started from, the history is replayed from here
+    starting_board: Board,
+    starting_turn: Color,
+    starting_fullmove: u32,
+    starting_halfmove_clock: u32,
+    board: Board,
+    turn: Color,
+    moves: Vec<Move>,
+    // Plies since the last pawn move or capture
+    halfmove_clock: u32,
+    // One entry per move played
+    undo_states: Vec<UndoState>,
+    // Zobrist hash of every position reached, starting with the initial one
+    history: Vec<u64>,
+    // How many times each hash in the history occurs
+    repetitions: HashMap<u64, usize>,
+    white_player: Option<String>,
+    black_player: Option<String>,
+    rated: bool,
+    // Pieces pawns may promote to, all four unless restricted for a variant
+    // or a drill
+    allowed_promotions: Vec<PromotionPiece>,
+    // A draw claimed in the current position, taking back a move drops it
+    claimed_draw: Option<DrawReason>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_position(Board::new_game(), Color::White)
+    }
+
+    pub fn from_position(board: Board, turn: Color) -> Self {
+        let hash = board.zobrist_hash(turn);
+        Game {
+            starting_board: board.clone(),
+            starting_turn: turn,
+            starting_fullmove: 1,
+            starting_halfmove_clock: 0,
+            board,
+            turn,
+            moves: Vec::new(),
+            halfmove_clock: 0,
+            undo_states: Vec::new(),
+            history: vec![hash],
+            repetitions: HashMap::from([(hash, 1)]),
+            white_player: None,
+            black_player: None,
+            rated: false,
+            allowed_promotions: PromotionPiece::ALL.to_vec(),
+            claimed_draw: None,
+        }
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, ChessError> {
+        Ok(Game::builder().fen(fen)?.build())
+    }
+
+    pub fn builder() -> GameBuilder {
+        GameBuilder::default()
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_player(&self, color: Color) -> Option<&str> {
+        match color {
+            Color::White => self.white_player.as_deref(),
+            Color::Black => self.black_player.as_deref(),
+        }
+    }
+
+    pub fn is_rated(&self) -> bool {
+        self.rated
+    }
+
+    pub fn get_allowed_promotions(&self) -> &[PromotionPiece] {
+        &self.allowed_promotions
+    }
+
+    // The legal moves of the side to move under this game's rules
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves = self.board.get_all_available_moves(self.turn);
+        moves.retain(|mv| {
+            mv.get_promotion()
+                .is_none_or(|promotion| self.allowed_promotions.contains(&promotion))
+        });
+        moves
+    }
+
+    pub fn get_turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn get_moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn get_ply_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    // The move played at a ply, counting from 0
+    pub fn get_move(&self, ply: usize) -> Option<&Move> {
+        self.moves.get(ply)
+    }
+
+    pub fn get_starting_position(&self) -> (&Board, Color) {
+        (&self.starting_board, self.starting_turn)
+    }
+
+    pub fn get_starting_fen(&self) -> String {
+        fen::to_fen(
+            &self.starting_board,
+            self.starting_turn,
+            self.starting_halfmove_clock,
+            self.starting_fullmove,
+        )
+    }
+
+    pub fn to_fen(&self) -> String {
+        fen::to_fen(
+            &self.board,
+            self.turn,
+            self.halfmove_clock,
+            self.get_fullmove_number(),
+        )
+    }
+
+    // Saved games are PGN, so only the players, the starting position and
+    // the moves are kept. The file is replaced in one step, a crash while
+    // saving leaves the previous save intact.
+    pub fn save(&self, path: &Path) -> Result<(), ChessError> {
+        let pgn = PgnGame::from_game(self).to_pgn(&PgnExportOptions::default());
+        let temp_path = temp_path(path);
+        let written = fs::File::create(&temp_path).and_then(|mut file| {
+            file.write_all(pgn.as_bytes())?;
+            // the data has to be on disk before the rename makes it the save
+            file.sync_all()
+        });
+        if let Err(err) = written.and_then(|_| fs::rename(&temp_path, path)) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+        sync_dir(path)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ChessError> {
+        pgn::parse_pgn(&fs::read(path)?, PgnMode::Strict)?
+            .games
+            .first()
+            .ok_or_else(|| ChessError::ParseError("no game in the file".to_string()))?
+            .to_game()
+    }
+
+    // The board before the move at `ply` was played, `get_ply_count()` gives
+    // the current one
+    pub fn get_board_at(&self, ply: usize) -> Option<Board> {
+        if ply > self.moves.len() {
+            return None;
+        }
+        let mut board = self.starting_board.clone();
+        for mv in &self.moves[..ply] {
+            board.make_move(mv).ok()?;
+        }
+        Some(board)
+    }
+
+    pub fn get_san_moves(&self) -> Vec<String> {
+        let mut board = self.starting_board.clone();
+        self.moves
+            .iter()
+            .map(|mv| {
+                // every move in the history was legal when it was played
+                let san = notation::move_to_san(&board, mv).unwrap();
+                board.make_move(mv).unwrap();
+                san
+            })
+            .collect()
+    }
+
+    // SAN for the side to move, e.g. "Nbd7", "exd5", "e8=Q+" or "O-O"
+    pub fn parse_san(&self, san: &str) -> Result<Move, ChessError> {
+        notation::move_from_san(&self.board, self.turn, san)
+    }
+
+    // Plays a move given in UCI, returning it with its details
+    pub fn push_uci(&mut self, uci: &str) -> Result<Move, ChessError> {
+        self.make_move(&uci.parse()?)?;
+        Ok(*self.moves.last().unwrap())
+    }
+
+    // Board::validate_move, plus the side to move and the end of the game
+    pub fn validate_move(&self, from: Position, to: Position) -> Result<(), ChessError> {
+        if !matches!(self.status(), GameStatus::Ongoing | GameStatus::Check) {
+            return Err(ChessError::GameOver);
+        }
+        match self.board.get_piece(from) {
+            None => Err(ChessError::NoPieceAtSquare(from)),
+            Some(piece) if piece.color != self.turn => Err(ChessError::WrongTurn),
+            Some(_) => self.board.validate_move(from, to),
+        }
+    }
+
+    // The only way to change the position: moves of the wrong side, illegal
+    // moves and moves after the game ended are refused
+    pub fn make_move(&mut self, mv: &Move) -> Result<(), ChessError> {
+        self.validate_move(mv.get_from(), mv.get_to())?;
+        if let Some(promotion) = mv.get_promotion() {
+            if !self.allowed_promotions.contains(&promotion) {
+                return Err(ChessError::PromotionNotAllowed(promotion));
+            }
+        }
+        let undo_state = UndoState {
+            castling_rights: self.board.castling_rights,
+            en_passant: self.board.en_passant,
+            halfmove_clock: self.halfmove_clock,
+        };
+        let mv = self.board.make_move(mv)?;
+        self.undo_states.push(undo_state);
+        let resets_clock = mv.is_capture()
+            || mv
+                .get_piece()
+                .is_some_and(|piece| matches!(piece.chess_piece, ChessPieceType::Pawn(_)));
+        if resets_clock {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.turn = self.turn.switch();
+        self.moves.push(mv);
+        let hash = self.board.zobrist_hash(self.turn);
+        self.history.push(hash);
+        *self.repetitions.entry(hash).or_insert(0) += 1;
+        Ok(())
+    }
+
+    // Takes back the last move, returning it
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        let undo_state = self.undo_states.pop().unwrap();
+        let hash = self.history.pop().unwrap();
+        if let Some(count) = self.repetitions.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.repetitions.remove(&hash);
+            }
+        }
+        self.turn = self.turn.switch();
+        self.claimed_draw = None;
+        self.board
+            .unmake_move(&mv, undo_state.castling_rights, undo_state.en_passant);
+        self.halfmove_clock = undo_state.halfmove_clock;
+        Some(mv)
+    }
+
+    // How many times the current position has occurred, including now
+    pub fn get_repetition_count(&self) -> usize {
+        let current = self.history.last().unwrap();
+        self.repetitions.get(current).copied().unwrap_or(0)
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.get_repetition_count() >= 3
+    }
+
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.get_repetition_count() >= 5
+    }
+
+    // A draw the side to move may claim, the game isn't over until it does
+    pub fn get_claimable_draw(&self) -> Option<DrawReason> {
+        if !matches!(self.status(), GameStatus::Ongoing | GameStatus::Check) {
+            None
+        } else if self.is_threefold_repetition() {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.is_fifty_move_rule() {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    // Ends the game in a draw when one can be claimed
+    pub fn claim_draw(&mut self) -> Option<DrawReason> {
+        let reason = self.get_claimable_draw()?;
+        self.claimed_draw = Some(reason);
+        Some(reason)
+    }
+
+    // Only automatic draws and claimed ones end the game, a mate delivered on
+    // the move that reaches an automatic draw still wins
+    pub fn status(&self) -> GameStatus {
+        let in_check = self.board.get_check_info(self.turn).is_some();
+        if self.board.get_all_available_moves(self.turn).is_empty() {
+            return if in_check {
+                GameStatus::Checkmate {
+                    winner: self.turn.switch(),
+                }
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        let draw_reason = if self.board.is_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.is_fivefold_repetition() {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.is_seventy_five_move_rule() {
+            Some(DrawReason::SeventyFiveMoveRule)
+        } else {
+            self.claimed_draw
+        };
+        match draw_reason {
+            Some(reason) => GameStatus::Draw { reason },
+            None if in_check => GameStatus::Check,
+            None => GameStatus::Ongoing,
+        }
+    }
+
+    // One ply lookahead, for warning about throwing away a won game
+    pub fn is_stalemating_move(&self, mv: &Move) -> bool {
+        let mut game = self.clone();
+        game.make_move(mv).is_ok() && game.status() == GameStatus::Stalemate
+    }
+
+    // Starts at 1 and goes up after every black move
+    pub fn get_fullmove_number(&self) -> u32 {
+        let black_started = u32::from(self.starting_turn == Color::Black);
+        self.starting_fullmove + (self.moves.len() as u32 + black_started) / 2
+    }
+
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.board.perft(self.turn, depth)
+    }
+
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        self.board.perft_divide(self.turn, depth)
+    }
+
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    // Fifty moves by each side, a draw either player can claim
+    pub fn is_fifty_move_rule(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    // Seventy-five moves by each side, drawn without a claim
+    pub fn is_seventy_five_move_rule(&self) -> bool {
+        self.halfmove_clock >= 150
+    }
+}
+
+// Games are stored as their starting position and moves, and replayed when
+// read back so a stored game can't reach a position play couldn't
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GameData {
+    starting_fen: String,
+    moves: Vec<String>,
+    white_player: Option<String>,
+    black_player: Option<String>,
+    rated: bool,
+    allowed_promotions: Vec<PromotionPiece>,
+    claimed_draw: Option<DrawReason>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Game {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameData {
+            starting_fen: self.get_starting_fen(),
+            moves: self.moves.iter().map(Move::to_uci).collect(),
+            white_player: self.white_player.clone(),
+            black_player: self.black_player.clone(),
+            rated: self.rated,
+            allowed_promotions: self.allowed_promotions.clone(),
+            claimed_draw: self.claimed_draw,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Game {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GameData::deserialize(deserializer)?;
+        let replay = || -> Result<Game, ChessError> {
+            let mut builder = Game::builder()
+                .fen(&data.starting_fen)?
+                .rated(data.rated)
+                .allowed_promotions(&data.allowed_promotions);
+            for (color, player) in [
+                (Color::White, &data.white_player),
+                (Color::Black, &data.black_player),
+            ] {
+                if let Some(player) = player {
+                    builder = builder.player(color, player);
+                }
+            }
+            let mut game = builder.build();
+            for uci in &data.moves {
+                game.push_uci(uci)?;
+            }
+            if let Some(reason) = data.claimed_draw {
+                if game.claim_draw() != Some(reason) {
+                    return Err(ChessError::InvalidPosition(format!(
+                        "no {:?} to claim",
+                        reason
+                    )));
+                }
+            }
+            Ok(game)
+        };
+        replay().map_err(serde::de::Error::custom)
+    }
+}
+
+// Sets up a game with several options at once, anything left unset gets the
+// same value as in Game::new
+#[derive(Clone, Default)]
+pub struct GameBuilder {
+    position: Option<(Board, Color)>,
+    fullmove_number: Option<u32>,
+    halfmove_clock: u32,
+    white_player: Option<String>,
+    black_player: Option<String>,
+    rated: bool,
+    allowed_promotions: Option<Vec<PromotionPiece>>,
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn position(mut self, board: Board, turn: Color) -> Self {
+        self.position = Some((board, turn));
+        self
+    }
+
+    // Number of the first move, for games continued from a later point
+    pub fn fullmove_number(mut self, fullmove_number: u32) -> Self {
+        self.fullmove_number = Some(fullmove_number);
+        self
+    }
+
+    // Position, move number and halfmove clock all at once
+    pub fn fen(mut self, fen: &str) -> Result<Self, ChessError> {
+        let position = fen::parse_fen(fen)?;
+        self.position = Some((position.board, position.turn));
+        self.fullmove_number = Some(position.fullmove_number);
+        self.halfmove_clock = position.halfmove_clock;
+        Ok(self)
+    }
+
+    pub fn player(mut self, color: Color, name: &str) -> Self {
+        let player = match color {
+            Color::White => &mut self.white_player,
+            Color::Black => &mut self.black_player,
+        };
+        *player = Some(name.to_string());
+        self
+    }
+
+    pub fn rated(mut self, rated: bool) -> Self {
+        self.rated = rated;
+        self
+    }
+
+    // An empty list is ignored, pawns always need something to promote to
+    pub fn allowed_promotions(mut self, promotions: &[PromotionPiece]) -> Self {
+        if !promotions.is_empty() {
+            self.allowed_promotions = Some(promotions.to_vec());
+        }
+        self
+    }
+
+    pub fn build(self) -> Game {
+        let (board, turn) = self
+            .position
+            .unwrap_or_else(|| (Board::new_game(), Color::White));
+        Game {
+            starting_fullmove: self.fullmove_number.unwrap_or(1).max(1),
+            starting_halfmove_clock: self.halfmove_clock,
+            halfmove_clock: self.halfmove_clock,
+            white_player: self.white_player,
+            black_player: self.black_player,
+            rated: self.rated,
+            allowed_promotions: self
+                .allowed_promotions
+                .unwrap_or_else(|| PromotionPiece::ALL.to_vec()),
+            ..Game::from_position(board, turn)
+        }
+    }
+}
+
+// A file next to the save that no other save, from this process or another
+// one, writes to at the same time
+fn temp_path(path: &Path) -> PathBuf {
+    static SAVES: AtomicUsize = AtomicUsize::new(0);
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        SAVES.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+// Makes the rename itself last through a crash
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+// Directories can't be opened as files here, the rename is as durable as it
+// gets
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNIGHT_SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            game.push_uci(uci).unwrap();
+        }
+    }
+
+    #[test]
+    fn threefold_repetition_is_claimed() {
+        let mut game = Game::new();
+        play(&mut game, &KNIGHT_SHUFFLE);
+        play(&mut game, &KNIGHT_SHUFFLE);
+        assert_eq!(game.get_repetition_count(), 3);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        assert_eq!(
+            game.get_claimable_draw(),
+            Some(DrawReason::ThreefoldRepetition)
+        );
+
+        // the game goes on if nobody claims
+        play(&mut game, &["e2e4"]);
+        assert_eq!(game.get_claimable_draw(), None);
+        game.unmake_move();
+
+        assert_eq!(game.claim_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw {
+                reason: DrawReason::ThreefoldRepetition
+            }
+        );
+        assert_eq!(game.push_uci("e2e4"), Err(ChessError::GameOver));
+    }
+
+    #[test]
+    fn unmake_restores_every_position() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/8/3pP3/8/8/R3K2R b KQkq e3 4 30").unwrap();
+        // en passant, both castlings and a promotion that takes a rook
+        let moves = ["d4e3", "e1c1", "e8g8", "b7a8q"];
+        let mut positions = Vec::new();
+        for uci in moves {
+            positions.push(game.to_fen());
+            game.push_uci(uci).unwrap();
+        }
+        assert_eq!(game.to_fen(), "Q4rk1/8/8/8/8/4p3/8/2KR3R b - - 0 32");
+
+        for uci in moves.iter().rev() {
+            assert_eq!(game.unmake_move().unwrap().to_uci(), *uci);
+            assert_eq!(game.to_fen(), positions.pop().unwrap());
+            assert_eq!(game.get_repetition_count(), 1);
+        }
+        assert_eq!(game.unmake_move(), None);
+    }
+
+    #[test]
+    fn moves_are_validated_for_the_side_to_move() {
+        let square = |name: &str| name.parse::<Position>().unwrap();
+        let mut game = Game::new();
+        assert_eq!(game.validate_move(square("e2"), square("e4")), Ok(()));
+        assert_eq!(
+            game.validate_move(square("e7"), square("e5")),
+            Err(ChessError::WrongTurn)
+        );
+        assert_eq!(
+            game.validate_move(square("e3"), square("e4")),
+            Err(ChessError::NoPieceAtSquare(square("e3")))
+        );
+        assert_eq!(
+            game.validate_move(square("e2"), square("e5")),
+            Err(ChessError::IllegalDestination)
+        );
+
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(
+            game.validate_move(square("e2"), square("e4")),
+            Err(ChessError::GameOver)
+        );
+        assert_eq!(
+            game.make_move(&"e2e4".parse().unwrap()),
+            Err(ChessError::GameOver)
+        );
+    }
+
+    #[test]
+    fn unmake_reopens_a_claimed_draw() {
+        let mut game = Game::new();
+        play(&mut game, &KNIGHT_SHUFFLE);
+        play(&mut game, &KNIGHT_SHUFFLE);
+        game.claim_draw().unwrap();
+        game.unmake_move();
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        assert_eq!(game.claim_draw(), None);
+    }
+
+    #[test]
+    fn nothing_to_claim_in_a_fresh_game() {
+        let mut game = Game::new();
+        assert_eq!(game.claim_draw(), None);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn fivefold_repetition_is_automatic() {
+        let mut game = Game::new();
+        for _ in 0..3 {
+            play(&mut game, &KNIGHT_SHUFFLE);
+        }
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        play(&mut game, &KNIGHT_SHUFFLE);
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw {
+                reason: DrawReason::FivefoldRepetition
+            }
+        );
+        assert_eq!(game.get_claimable_draw(), None);
+        assert_eq!(game.push_uci("e2e4"), Err(ChessError::GameOver));
+    }
+
+    #[test]
+    fn fifty_move_rule_is_claimed() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+        assert_eq!(game.get_claimable_draw(), None);
+        play(&mut game, &["a1a2"]);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        assert_eq!(game.get_claimable_draw(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(game.claim_draw(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw {
+                reason: DrawReason::FiftyMoveRule
+            }
+        );
+    }
+
+    #[test]
+    fn seventy_five_move_rule_is_automatic() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 80").unwrap();
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        play(&mut game, &["a1a2"]);
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw {
+                reason: DrawReason::SeventyFiveMoveRule
+            }
+        );
+    }
+
+    #[test]
+    fn pawn_moves_reset_the_clock() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 80").unwrap();
+        play(&mut game, &["e2e3"]);
+        assert_eq!(game.get_halfmove_clock(), 0);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn insufficient_material_is_automatic() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.status(), GameStatus::Check);
+        play(&mut game, &["e1d2"]);
+        assert_eq!(
+            game.status(),
+            GameStatus::Draw {
+                reason: DrawReason::InsufficientMaterial
+            }
+        );
+    }
+
+    #[test]
+    fn fen_round_trip_after_moves() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "c7c5", "e4e5", "d7d5"]);
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pp2pppp/8/2ppP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"
+        );
+        let copy = Game::from_fen(&game.to_fen()).unwrap();
+        assert_eq!(copy.to_fen(), game.to_fen());
+        assert_eq!(copy.get_legal_moves(), game.get_legal_moves());
+    }
+
+    #[test]
+    fn saved_games_are_loaded_back() {
+        let dir = std::env::temp_dir().join(format!("chess-save-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.pgn");
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "e7e5"]);
+        game.save(&path).unwrap();
+        play(&mut game, &["g1f3"]);
+        game.save(&path).unwrap();
+
+        assert_eq!(Game::load(&path).unwrap().get_moves(), game.get_moves());
+        // the temporary files are gone after the renames
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn games_are_replayed_when_deserialized() {
+        let mut game = Game::builder().player(Color::White, "Anna").build();
+        play(&mut game, &KNIGHT_SHUFFLE);
+        play(&mut game, &KNIGHT_SHUFFLE);
+        game.claim_draw().unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let copy = serde_json::from_str::<Game>(&json).unwrap();
+        assert_eq!(copy.to_fen(), game.to_fen());
+        assert_eq!(copy.status(), game.status());
+        assert_eq!(copy.get_player(Color::White), Some("Anna"));
+
+        // an illegal move, and a draw claimed before the third repetition
+        for (from, to) in [("g1f3", "g1g3"), (",\"f6g8\"]", "]")] {
+            let broken = json.replacen(from, to, 1);
+            assert!(serde_json::from_str::<Game>(&broken).is_err(), "{}", broken);
+        }
+    }
+}
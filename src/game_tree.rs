@@ -0,0 +1,441 @@
+use super::{ChessError, Game, Move};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Node {
+    // None for the root, the starting position
+    mv: Option<Move>,
+    parent: Option<usize>,
+    // The first child continues the line, the others are variations
+    children: Vec<usize>,
+}
+
+// A game with variations. Nodes are identified by their index, which stays
+// valid for the lifetime of the tree, even after its variation is removed.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "GameTreeData")
+)]
+pub struct GameTree {
+    nodes: Vec<Node>,
+    current: usize,
+    // The game at the current node
+    game: Game,
+}
+
+// A tree as it was stored, checked before it becomes a GameTree
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GameTreeData {
+    nodes: Vec<Node>,
+    current: usize,
+    game: Game,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GameTreeData> for GameTree {
+    type Error = ChessError;
+
+    // Every node has to hang below an earlier one and every line has to be
+    // playable from the position the stored game started the tree at. Moves
+    // are replayed, so their details are recomputed rather than trusted.
+    fn try_from(data: GameTreeData) -> Result<Self, Self::Error> {
+        let invalid =
+            |message: &str| ChessError::ParseError(format!("invalid game tree: {}", message));
+        let GameTreeData {
+            mut nodes,
+            current,
+            game,
+        } = data;
+        for (index, node) in nodes.iter().enumerate() {
+            let linked = match node.parent {
+                None => index == Self::ROOT && node.mv.is_none(),
+                Some(parent) => parent < index && node.mv.is_some(),
+            };
+            if !linked
+                || node
+                    .children
+                    .iter()
+                    .any(|&child| nodes.get(child).and_then(|child| child.parent) != Some(index))
+            {
+                return Err(invalid("broken links"));
+            }
+        }
+        if nodes.is_empty() || current >= nodes.len() {
+            return Err(invalid("no such node"));
+        }
+
+        let mut tree = GameTree {
+            nodes: Vec::new(),
+            current: Self::ROOT,
+            game: game.clone(),
+        };
+        for _ in tree_depth(&nodes, current) {
+            if tree.game.unmake_move().is_none() {
+                return Err(invalid("the game is shorter than the line"));
+            }
+        }
+        // depth first, playing each attached node's move from its parent
+        let mut stack = vec![(Self::ROOT, 0)];
+        while let Some((node, next_child)) = stack.pop() {
+            match nodes[node].children.get(next_child).copied() {
+                Some(child) => {
+                    stack.push((node, next_child + 1));
+                    tree.game.make_move(&nodes[child].mv.unwrap())?;
+                    nodes[child].mv = tree.game.get_moves().last().copied();
+                    stack.push((child, 0));
+                }
+                None if node != Self::ROOT => {
+                    tree.game.unmake_move();
+                }
+                None => {}
+            }
+        }
+        tree.nodes = nodes;
+        if !tree.go_to(current) {
+            return Err(invalid("the current node was removed"));
+        }
+        if tree.game.get_moves() != game.get_moves() {
+            return Err(invalid("the game doesn't follow the line"));
+        }
+        Ok(tree)
+    }
+}
+
+// The moves between the root and the node
+#[cfg(feature = "serde")]
+fn tree_depth(nodes: &[Node], mut node: usize) -> std::ops::Range<usize> {
+    let mut depth = 0;
+    while let Some(parent) = nodes[node].parent {
+        node = parent;
+        depth += 1;
+    }
+    0..depth
+}
+
+impl GameTree {
+    pub const ROOT: usize = 0;
+
+    // The game's moves become the main line, the tree starts at its last move
+    pub fn new(game: Game) -> Self {
+        let mut nodes = vec![Node {
+            mv: None,
+            parent: None,
+            children: Vec::new(),
+        }];
+        for (ply, mv) in game.get_moves().iter().enumerate() {
+            nodes[ply].children.push(ply + 1);
+            nodes.push(Node {
+                mv: Some(*mv),
+                parent: Some(ply),
+                children: Vec::new(),
+            });
+        }
+        GameTree {
+            current: nodes.len() - 1,
+            nodes,
+            game,
+        }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_current(&self) -> usize {
+        self.current
+    }
+
+    pub fn get_move(&self, node: usize) -> Option<&Move> {
+        self.nodes.get(node)?.mv.as_ref()
+    }
+
+    pub fn get_parent(&self, node: usize) -> Option<usize> {
+        self.nodes.get(node)?.parent
+    }
+
+    pub fn get_children(&self, node: usize) -> &[usize] {
+        self.nodes
+            .get(node)
+            .map(|node| node.children.as_slice())
+            .unwrap_or_default()
+    }
+
+    // The nodes from the root down to this one
+    fn get_path(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(parent) = self.nodes[path[path.len() - 1]].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn is_main_line(&self, node: usize) -> bool {
+        node < self.nodes.len()
+            && self
+                .get_path(node)
+                .windows(2)
+                .all(|pair| self.nodes[pair[0]].children.first() == Some(&pair[1]))
+    }
+
+    // The moves leading to the node
+    pub fn get_line(&self, node: usize) -> Vec<Move> {
+        if node >= self.nodes.len() {
+            return Vec::new();
+        }
+        self.get_path(node)
+            .iter()
+            .filter_map(|&node| self.nodes[node].mv)
+            .collect()
+    }
+
+    pub fn get_main_line(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut node = Self::ROOT;
+        while let Some(&child) = self.nodes[node].children.first() {
+            moves.extend(self.nodes[child].mv);
+            node = child;
+        }
+        moves
+    }
+
+    // The game played along the main line to its end
+    pub fn get_main_line_game(&self) -> Game {
+        let mut tree = self.clone();
+        tree.go_to(Self::ROOT);
+        while tree.go_forward() {}
+        tree.game
+    }
+
+    // Follows the move if it was already played here, otherwise it starts a
+    // new line, which is the main line when it's the first move played here
+    pub fn make_move(&mut self, mv: &Move) -> Result<(), ChessError> {
+        let existing = self.nodes[self.current]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].mv.as_ref() == Some(mv));
+        self.game.make_move(mv)?;
+        self.current = match existing {
+            Some(child) => child,
+            None => {
+                let child = self.nodes.len();
+                self.nodes.push(Node {
+                    mv: self.game.get_moves().last().copied(),
+                    parent: Some(self.current),
+                    children: Vec::new(),
+                });
+                self.nodes[self.current].children.push(child);
+                child
+            }
+        };
+        Ok(())
+    }
+
+    pub fn go_back(&mut self) -> bool {
+        match self.nodes[self.current].parent {
+            Some(parent) => {
+                self.game.unmake_move();
+                self.current = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Follows the line the current node continues with
+    pub fn go_forward(&mut self) -> bool {
+        let Some(&child) = self.nodes[self.current].children.first() else {
+            return false;
+        };
+        // the move was legal when it was added to the tree
+        let mv = self.nodes[child].mv.unwrap();
+        self.game.make_move(&mv).unwrap();
+        self.current = child;
+        true
+    }
+
+    // Returns false for nodes that aren't in the tree
+    pub fn go_to(&mut self, node: usize) -> bool {
+        if node >= self.nodes.len() || !self.is_attached(node) {
+            return false;
+        }
+        let path = self.get_path(node);
+        while !path.contains(&self.current) {
+            self.go_back();
+        }
+        let start = path.iter().position(|&n| n == self.current).unwrap();
+        for &next in &path[start + 1..] {
+            let mv = self.nodes[next].mv.unwrap();
+            self.game.make_move(&mv).unwrap();
+            self.current = next;
+        }
+        true
+    }
+
+    // Removed variations keep their nodes but can no longer be reached
+    fn is_attached(&self, node: usize) -> bool {
+        self.get_path(node)
+            .windows(2)
+            .all(|pair| self.nodes[pair[0]].children.contains(&pair[1]))
+    }
+
+    // Makes the node's line the one its parent continues with
+    pub fn promote_variation(&mut self, node: usize) {
+        let Some(parent) = self.get_parent(node) else {
+            return;
+        };
+        let children = &mut self.nodes[parent].children;
+        if let Some(index) = children.iter().position(|&child| child == node) {
+            children.remove(index);
+            children.insert(0, node);
+        }
+    }
+
+    // Drops the node and everything after it, stepping out of it first if
+    // the current position is inside it
+    pub fn remove_variation(&mut self, node: usize) {
+        let Some(parent) = self.get_parent(node) else {
+            return;
+        };
+        if self.get_path(self.current).contains(&node) {
+            self.go_to(parent);
+        }
+        self.nodes[parent].children.retain(|&child| child != node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uci(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    fn ucis(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(Move::to_uci).collect()
+    }
+
+    // 1. e4 e5 (1... c5 2. Nf3) 2. Nf3, at the end of the main line
+    fn sicilian_aside() -> (GameTree, usize) {
+        let mut tree = GameTree::new(Game::new());
+        for text in ["e2e4", "e7e5", "g1f3"] {
+            tree.make_move(&uci(text)).unwrap();
+        }
+        let end = tree.get_current();
+        let after_e4 = tree.get_children(GameTree::ROOT)[0];
+        tree.go_to(after_e4);
+        for text in ["c7c5", "g1f3"] {
+            tree.make_move(&uci(text)).unwrap();
+        }
+        tree.go_to(end);
+        (tree, after_e4)
+    }
+
+    #[test]
+    fn moves_are_followed_or_branch_off() {
+        let (mut tree, after_e4) = sicilian_aside();
+        assert_eq!(ucis(&tree.get_main_line()), ["e2e4", "e7e5", "g1f3"]);
+        assert_eq!(tree.get_children(after_e4).len(), 2);
+
+        // playing a move that is already there follows it
+        let nodes = tree.nodes.len();
+        tree.go_to(after_e4);
+        tree.make_move(&uci("e7e5")).unwrap();
+        assert_eq!(tree.nodes.len(), nodes);
+        assert!(tree.is_main_line(tree.get_current()));
+        assert!(tree.make_move(&uci("e7e5")).is_err());
+        assert_eq!(tree.nodes.len(), nodes);
+    }
+
+    #[test]
+    fn navigation_replays_the_line() {
+        let (mut tree, after_e4) = sicilian_aside();
+        let sicilian = tree.get_children(after_e4)[1];
+        let nf3 = tree.get_children(sicilian)[0];
+        assert!(!tree.is_main_line(nf3));
+        assert_eq!(ucis(&tree.get_line(nf3)), ["e2e4", "c7c5", "g1f3"]);
+
+        // straight from one line to the other
+        assert!(tree.go_to(nf3));
+        assert_eq!(ucis(tree.get_game().get_moves()), ["e2e4", "c7c5", "g1f3"]);
+        assert!(tree.go_back());
+        assert_eq!(tree.get_current(), sicilian);
+        assert!(tree.go_forward());
+        assert!(!tree.go_forward());
+
+        assert!(tree.go_to(GameTree::ROOT));
+        assert!(!tree.go_back());
+        assert_eq!(tree.get_game().get_ply_count(), 0);
+        assert!(!tree.go_to(tree.nodes.len()));
+        assert_eq!(
+            ucis(tree.get_main_line_game().get_moves()),
+            ["e2e4", "e7e5", "g1f3"]
+        );
+    }
+
+    #[test]
+    fn variations_are_promoted_and_removed() {
+        let (mut tree, after_e4) = sicilian_aside();
+        let sicilian = tree.get_children(after_e4)[1];
+        let nf3 = tree.get_children(sicilian)[0];
+        tree.promote_variation(sicilian);
+        assert_eq!(ucis(&tree.get_main_line()), ["e2e4", "c7c5", "g1f3"]);
+        assert!(tree.is_main_line(nf3));
+
+        // removing the line the tree is in steps back to where it started
+        tree.go_to(nf3);
+        tree.remove_variation(sicilian);
+        assert_eq!(tree.get_current(), after_e4);
+        assert_eq!(ucis(&tree.get_main_line()), ["e2e4", "e7e5", "g1f3"]);
+        assert!(!tree.go_to(nf3));
+        assert_eq!(tree.get_children(after_e4).len(), 1);
+
+        // the root can't be removed
+        tree.remove_variation(GameTree::ROOT);
+        assert_eq!(tree.get_children(GameTree::ROOT).len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn trees_are_checked_when_deserialized() {
+        let mut game = Game::new();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            game.push_uci(uci).unwrap();
+        }
+        let mut tree = GameTree::new(game);
+        tree.go_back();
+        let mut variation = tree.get_game().clone();
+        variation.push_uci("b1c3").unwrap();
+        tree.make_move(variation.get_moves().last().unwrap())
+            .unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let copy = serde_json::from_str::<GameTree>(&json).unwrap();
+        assert_eq!(copy.get_current(), tree.get_current());
+        assert_eq!(copy.get_main_line(), tree.get_main_line());
+        assert_eq!(copy.get_game().to_fen(), tree.get_game().to_fen());
+
+        for (from, to) in [
+            ("\"current\":4", "\"current\":5"),
+            ("\"children\":[3,4]", "\"children\":[4,3,4,1]"),
+            ("\"to\":\"c3\"", "\"to\":\"c4\""),
+            (
+                "\"moves\":[\"e2e4\",\"e7e5\",\"b1c3\"]",
+                "\"moves\":[\"e2e4\",\"e7e5\",\"g1f3\"]",
+            ),
+        ] {
+            let broken = json.replacen(from, to, 1);
+            assert_ne!(broken, json);
+            assert!(
+                serde_json::from_str::<GameTree>(&broken).is_err(),
+                "{}",
+                broken
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use super::{Game, Move, Result};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+// Runs on the game and returns what sends the answer back
+type Request = Box<dyn FnOnce(&mut Game) -> Box<dyn FnOnce() + Send> + Send>;
+
+enum Command {
+    Run(Request),
+    Subscribe(Sender<Move>),
+}
+
+// A game owned by its own thread, driven through messages so callers never
+// block on it, which suits bots and servers running on an async runtime.
+// Clones drive the same game, the thread stops once they are all dropped.
+#[derive(Clone)]
+pub struct GameHandle {
+    commands: Sender<Command>,
+}
+
+// The answer to a request, available once the game's thread has run it
+pub struct Pending<T> {
+    reply: Receiver<T>,
+    // kept once try_get has seen it, so it can still be taken by wait
+    ready: Option<T>,
+}
+
+impl<T> Pending<T> {
+    // Doesn't block
+    pub fn is_ready(&mut self) -> bool {
+        if self.ready.is_none() {
+            self.ready = self.reply.try_recv().ok();
+        }
+        self.ready.is_some()
+    }
+
+    // Doesn't block, None while the request is still queued or if the game's
+    // thread stopped before running it
+    pub fn try_get(&mut self) -> Option<T> {
+        self.ready.take().or_else(|| self.reply.try_recv().ok())
+    }
+
+    // Blocks until the request has run, None if it never will
+    pub fn wait(mut self) -> Option<T> {
+        self.ready.take().or_else(|| self.reply.recv().ok())
+    }
+}
+
+impl GameHandle {
+    pub fn spawn(game: Game) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        thread::spawn(move || run(game, receiver));
+        GameHandle { commands }
+    }
+
+    pub fn make_move(&self, mv: Move) -> Pending<Result<()>> {
+        self.update(move |game| game.make_move(&mv))
+    }
+
+    pub fn query<T, F>(&self, f: F) -> Pending<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Game) -> T + Send + 'static,
+    {
+        self.update(move |game| f(game))
+    }
+
+    // Runs f on the game's thread, in the order the requests were sent
+    pub fn update<T, F>(&self, f: F) -> Pending<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Game) -> T + Send + 'static,
+    {
+        let (reply, receiver) = mpsc::channel();
+        let request: Request = Box::new(move |game| {
+            let answer = f(game);
+            Box::new(move || {
+                // nobody waiting for the answer is fine
+                let _ = reply.send(answer);
+            })
+        });
+        // if the thread is gone the reply's sender is dropped with the
+        // request, so Pending reports it
+        let _ = self.commands.send(Command::Run(request));
+        Pending {
+            reply: receiver,
+            ready: None,
+        }
+    }
+
+    // Every move played after this, by any handle, is sent to the observer.
+    // Taking moves back isn't reported, the next move played is.
+    pub fn subscribe(&self) -> Receiver<Move> {
+        let (observer, receiver) = mpsc::channel();
+        let _ = self.commands.send(Command::Subscribe(observer));
+        receiver
+    }
+}
+
+fn run(mut game: Game, commands: Receiver<Command>) {
+    let mut observers: Vec<Sender<Move>> = Vec::new();
+    for command in commands {
+        match command {
+            Command::Run(request) => {
+                let ply = game.get_ply_count();
+                let answer = request(&mut game);
+                // observers hear about the moves before the caller gets its
+                // answer, those that hung up are dropped
+                let played = game.get_moves().get(ply..).unwrap_or_default();
+                observers.retain(|observer| played.iter().all(|mv| observer.send(*mv).is_ok()));
+                answer();
+            }
+            Command::Subscribe(observer) => observers.push(observer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, GameStatus};
+
+    fn uci(text: &str) -> Move {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn requests_run_in_order() {
+        let handle = GameHandle::spawn(Game::new());
+        let first = handle.make_move(uci("e2e4"));
+        let illegal = handle.make_move(uci("e2e4"));
+        let turn = handle.query(|game| game.get_turn());
+        assert!(first.wait().unwrap().is_ok());
+        assert!(illegal.wait().unwrap().is_err());
+        assert_eq!(turn.wait(), Some(Color::Black));
+    }
+
+    #[test]
+    fn observers_see_every_move() {
+        let handle = GameHandle::spawn(Game::new());
+        let moves = handle.subscribe();
+        let other = handle.clone();
+        for text in ["f2f3", "e7e5", "g2g4"] {
+            other.make_move(uci(text));
+        }
+        // taken back, then played again
+        handle.update(|game| game.unmake_move());
+        let mate = handle.update(|game| {
+            game.push_uci("g2g4").unwrap();
+            game.push_uci("d8h4").unwrap();
+            game.status()
+        });
+        assert!(matches!(mate.wait(), Some(GameStatus::Checkmate { .. })));
+
+        let seen: Vec<String> = moves.try_iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(seen, ["f2f3", "e7e5", "g2g4", "g2g4", "d8h4"]);
+    }
+
+    #[test]
+    fn answers_can_be_polled() {
+        let handle = GameHandle::spawn(Game::new());
+        let (release, blocked) = mpsc::channel::<()>();
+        // holds the game's thread until the test lets it go
+        handle.update(move |_| blocked.recv().unwrap());
+        let mut count = handle.query(|game| game.get_legal_moves().len());
+        assert!(!count.is_ready());
+        assert_eq!(count.try_get(), None);
+        release.send(()).unwrap();
+        assert_eq!(count.wait(), Some(20));
+    }
+}
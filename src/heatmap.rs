@@ -0,0 +1,173 @@
+use super::pgn::ImportedGame;
+use super::{fen, Board, ChessPieceType, Color, Game, Move, Position};
+
+// How often a kind of piece stands on each square, summed over every
+// position reached after an opening line
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    piece: ChessPieceType,
+    color: Color,
+    // the FEN fields games have to start from, without the move counters
+    start: String,
+    opening: Vec<Move>,
+    counts: [[u32; 8]; 8],
+    games: usize,
+}
+
+// The fields that tell two starting positions apart, the move counters
+// don't
+fn position_fields(fen: &str) -> String {
+    fen.split_whitespace()
+        .take(4)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+impl Heatmap {
+    // The opening is played from the standard starting position
+    pub fn new(piece: ChessPieceType, color: Color, opening: Vec<Move>) -> Self {
+        Heatmap {
+            opening,
+            ..Self::from_game(piece, color, &Game::new())
+        }
+    }
+
+    // The game's moves are the opening, and games have to start from the
+    // position it started from
+    pub fn from_game(piece: ChessPieceType, color: Color, game: &Game) -> Self {
+        Heatmap {
+            piece,
+            color,
+            start: position_fields(&game.get_starting_fen()),
+            opening: game.get_moves().to_vec(),
+            counts: Default::default(),
+            games: 0,
+        }
+    }
+
+    // Games that don't start from the same position with the opening line or
+    // can't be replayed from their starting position are ignored
+    pub fn add_game(&mut self, imported: &ImportedGame) {
+        if !imported.moves.starts_with(&self.opening) {
+            return;
+        }
+
+        let (mut board, turn) = match imported.game.get_tag("FEN") {
+            Some(fen) => match fen::parse_fen(fen) {
+                Ok(position) => (position.board, position.turn),
+                Err(_) => return,
+            },
+            None => (Board::new_game(), Color::White),
+        };
+        // written back out so the en passant square is given the same way
+        if position_fields(&fen::to_fen(&board, turn, 0, 1)) != self.start {
+            return;
+        }
+        let mut counts = [[0; 8]; 8];
+        for (ply, mv) in imported.moves.iter().enumerate() {
+            if board.make_move(mv).is_err() {
+                return;
+            }
+            if ply + 1 < self.opening.len() {
+                continue;
+            }
+            for (row, squares) in board.squares.iter().enumerate() {
+                for (column, square) in squares.iter().enumerate() {
+                    if square
+                        .as_ref()
+                        .is_some_and(|p| p.color == self.color && p.chess_piece == self.piece)
+                    {
+                        counts[row][column] += 1;
+                    }
+                }
+            }
+        }
+        for (row, squares) in counts.iter().enumerate() {
+            for (column, count) in squares.iter().enumerate() {
+                self.counts[row][column] += count;
+            }
+        }
+        self.games += 1;
+    }
+
+    pub fn get_games(&self) -> usize {
+        self.games
+    }
+
+    pub fn get_count(&self, pos: Position) -> u32 {
+        self.counts[pos.get_row()][pos.get_column()]
+    }
+
+    // Count relative to the busiest square, between 0 and 1
+    pub fn get_intensity(&self, pos: Position) -> f32 {
+        let max = self.counts.iter().flatten().copied().max().unwrap_or(0);
+        if max == 0 {
+            return 0.0;
+        }
+        self.get_count(pos) as f32 / max as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::{PgnMode, PgnReader};
+    use crate::Knight;
+
+    fn imported(pgn: &str) -> ImportedGame {
+        let game = PgnReader::new(pgn.as_bytes(), PgnMode::Lenient)
+            .next()
+            .unwrap()
+            .unwrap();
+        let moves = game.validate().unwrap();
+        ImportedGame { game, moves }
+    }
+
+    fn knights_after(game: &Game) -> Heatmap {
+        Heatmap::from_game(ChessPieceType::Knight(Knight), Color::White, game)
+    }
+
+    fn square(name: &str) -> Position {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn counts_games_following_the_opening() {
+        let mut game = Game::new();
+        game.push_uci("g1f3").unwrap();
+        let mut heatmap = knights_after(&game);
+        heatmap.add_game(&imported("1. Nf3 Nf6 2. Nc3 *"));
+        heatmap.add_game(&imported("1. e4 e5 *"));
+        // the same position, only the move counters differ
+        heatmap.add_game(&imported(
+            "[SetUp \"1\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4 20\"]\n\n20. Nf3 *",
+        ));
+        heatmap.add_game(&imported(
+            "[SetUp \"1\"]\n[FEN \"4k3/4p3/8/8/8/8/8/4K1N1 w - - 0 1\"]\n\n1. Nf3 *",
+        ));
+
+        assert_eq!(heatmap.get_games(), 2);
+        assert_eq!(heatmap.get_count(square("f3")), 4);
+        assert_eq!(heatmap.get_count(square("b1")), 3);
+        assert_eq!(heatmap.get_count(square("c3")), 1);
+        assert_eq!(heatmap.get_count(square("g1")), 0);
+        assert_eq!(heatmap.get_intensity(square("f3")), 1.0);
+        assert_eq!(heatmap.get_intensity(square("c3")), 0.25);
+    }
+
+    #[test]
+    fn games_have_to_start_from_the_same_position() {
+        let endgame = "[SetUp \"1\"]\n[FEN \"4k3/4p3/8/8/8/8/8/4K1N1 w - - 0 1\"]\n\n1. Nf3 *";
+        let mut game = Game::from_fen("4k3/4p3/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        game.push_uci("g1f3").unwrap();
+        let mut heatmap = knights_after(&game);
+        heatmap.add_game(&imported(endgame));
+        heatmap.add_game(&imported("1. Nf3 *"));
+        assert_eq!(heatmap.get_games(), 1);
+        assert_eq!(heatmap.get_count(square("f3")), 1);
+
+        let mut standard = Heatmap::new(ChessPieceType::Knight(Knight), Color::White, Vec::new());
+        standard.add_game(&imported(endgame));
+        assert_eq!(standard.get_games(), 0);
+    }
+}
@@ -1,33 +1,133 @@
 use std::{fmt, fmt::Debug};
 
-type Result<T> = std::result::Result<T, MoveError>;
-
-// Define our error types. These may be customized for our error handling cases.
-// Now we will be able to write our own errors, defer to an underlying error
-// implementation, or do something in between.
-#[derive(Debug, Clone)]
-pub struct MoveError;
-
-// Generation of an error is completely separate from how it is displayed.
-// There's no need to be concerned about cluttering complex logic with the display style.
-//
-// Note that we don't store any extra info about the errors. This means we can't state
-// which string failed to parse without modifying our types to carry that information.
-impl fmt::Display for MoveError {
+type Result<T> = std::result::Result<T, ChessError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChessError {
+    // Coordinates off the board, they may be negative after an offset
+    OutOfBounds { row: isize, column: isize },
+    NoPieceAtSquare(Position),
+    WrongTurn,
+    // The piece doesn't move that way, or a promotion piece is missing
+    IllegalDestination,
+    OwnPieceOnTarget,
+    BlockedPath,
+    KingLeftInCheck,
+    CastlingNotAllowed,
+    // The game's rules don't allow promoting to that piece
+    PromotionNotAllowed(PromotionPiece),
+    GameOver,
+    MissingKing(Color),
+    // Notation that fits more than one legal move
+    AmbiguousMove(String),
+    // What couldn't be parsed, and where when that's known
+    ParseError(String),
+    // Well formed, but not a position that can arise in a game
+    InvalidPosition(String),
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for ChessError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid move")
+        match self {
+            ChessError::OutOfBounds { row, column } => {
+                write!(f, "row {} column {} is off the board", row, column)
+            }
+            ChessError::NoPieceAtSquare(pos) => write!(f, "there is no piece on {}", pos),
+            ChessError::WrongTurn => write!(f, "it is the other side's turn"),
+            ChessError::IllegalDestination => write!(f, "the piece can't move there"),
+            ChessError::OwnPieceOnTarget => write!(f, "the square holds a piece of the same side"),
+            ChessError::BlockedPath => write!(f, "another piece is in the way"),
+            ChessError::KingLeftInCheck => write!(f, "the move would leave the king in check"),
+            ChessError::CastlingNotAllowed => write!(f, "castling isn't allowed here"),
+            ChessError::PromotionNotAllowed(piece) => {
+                write!(f, "promoting to a {:?} isn't allowed", piece)
+            }
+            ChessError::GameOver => write!(f, "the game is over"),
+            ChessError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            ChessError::AmbiguousMove(notation) => write!(f, "{} matches several moves", notation),
+            ChessError::ParseError(message) => write!(f, "parse error: {}", message),
+            ChessError::InvalidPosition(message) => write!(f, "invalid position: {}", message),
+            ChessError::Io(kind) => write!(f, "i/o error: {}", kind),
+        }
     }
 }
 
-#[derive(Default, Clone)]
+impl std::error::Error for ChessError {}
+
+impl From<std::io::Error> for ChessError {
+    fn from(error: std::io::Error) -> Self {
+        ChessError::Io(error.kind())
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
 pub struct Board {
-    pub squares: [[Option<ChessPiece>; 8]; 8],
+    // Written through set_square only, so the material counts stay in step
+    squares: [[Option<ChessPiece>; 8]; 8],
+    pub castling_rights: CastlingRights,
+    // Square skipped by a pawn's double push on the previous move
+    pub en_passant: Option<Position>,
+    // White's and black's pieces, updated on every square write
+    material: [MaterialCount; 2],
+}
+
+// The king of the side in check together with every piece giving check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckInfo {
+    pub king: Position,
+    pub checkers: Vec<Position>,
+}
+
+// An absolutely pinned piece can only move along the ray between its king
+// and the pinning piece, the pinner included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    pub pinned: Position,
+    pub pinner: Position,
+    pub ray: Vec<Position>,
 }
 
 mod position;
 pub use position::Position;
 
+mod castling;
+pub use castling::{CastlingRights, CastlingSide};
+
+mod chess_move;
+pub use chess_move::{Move, PromotionPiece};
+
+mod game;
+pub use game::{DrawReason, Game, GameBuilder, GameStatus};
+
+mod game_tree;
+pub use game_tree::GameTree;
+
+mod material;
+pub use material::MaterialCount;
+
+mod pawn_structure;
+
+mod perft;
+
+mod zobrist;
+
+pub mod epd;
+pub mod fen;
+pub mod handle;
+pub mod heatmap;
+pub mod notation;
+pub mod opening_tree;
+pub mod pgn;
+pub mod polyglot;
+pub mod problem;
+pub mod progress;
+pub mod report;
+pub mod share;
+pub mod simulation;
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     Black,
     #[default]
@@ -44,28 +144,32 @@ impl Color {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pawn;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Knight;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Queen;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct King;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rook;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bishop;
 
-#[derive(Debug, Clone)]
-struct Piece;
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ChessPieceType {
     Pawn(Pawn),
     Knight(Knight),
@@ -75,7 +179,8 @@ pub enum ChessPieceType {
     King(King),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChessPiece {
     pub color: Color,
     chess_piece: ChessPieceType,
@@ -84,17 +189,58 @@ pub struct ChessPiece {
 const WHITE_PAWN_ROW: usize = 1;
 const BLACK_PAWN_ROW: usize = 6;
 const LAST_ROW: usize = 7;
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+// The squares in each of the eight directions, nearest first, and whether
+// the direction is diagonal
+fn get_rays(pos: Position) -> [(Vec<Position>, bool); 8] {
+    [
+        (pos.get_left_squares(), false),
+        (pos.get_right_squares(), false),
+        (pos.get_up_squares(), false),
+        (pos.get_down_squares(), false),
+        (pos.get_principal_diagonal_up_squares(), true),
+        (pos.get_principal_diagonal_down_squares(), true),
+        (pos.get_secondary_diagonal_up_squares(), true),
+        (pos.get_secondary_diagonal_down_squares(), true),
+    ]
+}
+
+fn slides_along(chess_piece: &ChessPieceType, diagonal: bool) -> bool {
+    match chess_piece {
+        ChessPieceType::Queen(_) => true,
+        ChessPieceType::Bishop(_) => diagonal,
+        ChessPieceType::Rook(_) => !diagonal,
+        _ => false,
+    }
+}
 
 impl ChessPiece {
     pub fn new(chess_piece: ChessPieceType, color: Color) -> Self {
         ChessPiece { color, chess_piece }
     }
+
+    pub fn get_type(&self) -> ChessPieceType {
+        self.chess_piece
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
         Board {
             squares: Default::default(),
+            castling_rights: Default::default(),
+            en_passant: None,
+            material: Default::default(),
         }
     }
 
@@ -156,6 +302,9 @@ impl Board {
             Color::Black,
         ));
         last_row[7] = Some(ChessPiece::new(ChessPieceType::Rook(Rook), Color::Black));
+
+        self.castling_rights = CastlingRights::all();
+        self.recount_material();
     }
 
     pub fn get_piece(&self, pos: Position) -> &Option<ChessPiece> {
@@ -163,13 +312,59 @@ impl Board {
     }
 
     pub fn add_piece(&mut self, piece: ChessPiece, pos: Position) -> Result<()> {
-        self.squares[pos.get_row()][pos.get_column()] = Some(piece);
+        self.set_square(pos.get_row(), pos.get_column(), Some(piece));
         Ok(())
     }
 
+    pub fn remove_piece(&mut self, pos: Position) -> Option<ChessPiece> {
+        self.take_square(pos.get_row(), pos.get_column())
+    }
+
     pub fn move_piece(&mut self, initial_position: Position, final_position: Position) {
-        self.squares[final_position.get_row()][final_position.get_column()] =
-            self.squares[initial_position.get_row()][initial_position.get_column()].take();
+        if let Some(piece) = self.get_piece(initial_position) {
+            if let ChessPieceType::King(_) = piece.chess_piece {
+                self.castling_rights.remove_all(piece.color);
+                // A king moving two squares is castling, the rook jumps over it
+                if let Some(side) = CastlingSide::from_king_move(initial_position, final_position) {
+                    let row = initial_position.get_row();
+                    let rook = self.take_square(row, side.get_rook_column());
+                    self.set_square(row, side.get_rook_destination_column(), rook);
+                }
+            }
+        }
+        self.castling_rights.remove_for_square(initial_position);
+        self.castling_rights.remove_for_square(final_position);
+
+        let mut en_passant = None;
+        if let Some(piece) = self.get_piece(initial_position) {
+            if let ChessPieceType::Pawn(_) = piece.chess_piece {
+                // The captured pawn sits beside the capturing one, not on the target square
+                if Some(final_position) == self.en_passant
+                    && initial_position.get_column() != final_position.get_column()
+                {
+                    self.set_square(
+                        initial_position.get_row(),
+                        final_position.get_column(),
+                        None,
+                    );
+                }
+                if initial_position
+                    .get_row()
+                    .abs_diff(final_position.get_row())
+                    == 2
+                {
+                    en_passant = Position::try_new(
+                        (initial_position.get_row() + final_position.get_row()) / 2,
+                        initial_position.get_column(),
+                    )
+                    .ok();
+                }
+            }
+        }
+        self.en_passant = en_passant;
+
+        let piece = self.take_square(initial_position.get_row(), initial_position.get_column());
+        self.set_square(final_position.get_row(), final_position.get_column(), piece);
     }
 
     fn get_orthogonal_moves(&self, piece: &ChessPiece, pos: Position) -> Vec<Position> {
@@ -270,19 +465,6 @@ impl Board {
         available_moves
     }
 
-    fn get_attacking_squares(&self, pos: Position, color: Color) -> Vec<Position> {
-        let mut moves = Vec::<Position>::new();
-        if let Some(piece) = self.get_piece(pos) {
-            if piece.color == color {
-                moves.extend(match &piece.chess_piece {
-                    ChessPieceType::Pawn(p) => p.get_attacking_squares(pos, piece.color),
-                    _ => self.get_moves_ignoring_check(pos),
-                });
-            }
-        }
-        moves
-    }
-
     pub fn move_piece_on_shadow_board(
         &self,
         initial_position: Position,
@@ -302,19 +484,281 @@ impl Board {
         positions.retain(|&possible_possition| {
             let shadow_board =
                 self.move_piece_on_shadow_board(original_position, possible_possition);
-            shadow_board.is_king_in_check(color).is_none()
+            // without a king there's no check to walk into
+            !matches!(shadow_board.is_king_in_check(color), Ok(Some(_)))
         });
 
         positions
     }
 
-    pub fn get_available_moves(&mut self, pos: Position) -> Vec<Position> {
+    // Every piece of `by_color` that attacks the square, whatever stands on it.
+    // Pieces defending their own side count too.
+    pub fn attackers_of(&self, target: Position, by_color: Color) -> Vec<Position> {
+        let mut attackers = Vec::new();
+        let mut add_if = |pos: Position, is_attacker: &dyn Fn(&ChessPieceType) -> bool| {
+            if let Some(piece) = self.get_piece(pos) {
+                if piece.color == by_color && is_attacker(&piece.chess_piece) {
+                    attackers.push(pos);
+                }
+            }
+        };
+
+        for (squares, diagonal) in get_rays(target) {
+            if let Some(&square) = squares.iter().find(|&&sq| self.get_piece(sq).is_some()) {
+                add_if(square, &|chess_piece| slides_along(chess_piece, diagonal));
+            }
+        }
+        for offset in KNIGHT_OFFSETS {
+            if let Ok(square) = target.try_add(offset) {
+                add_if(square, &|chess_piece| {
+                    matches!(chess_piece, ChessPieceType::Knight(_))
+                });
+            }
+        }
+        for square in target.get_surrounding_squares() {
+            add_if(square, &|chess_piece| {
+                matches!(chess_piece, ChessPieceType::King(_))
+            });
+        }
+        // the pawns attacking a square sit where a pawn of the other color on it would attack
+        for square in Pawn.get_attacking_squares(target, by_color.switch()) {
+            add_if(square, &|chess_piece| {
+                matches!(chess_piece, ChessPieceType::Pawn(_))
+            });
+        }
+        attackers
+    }
+
+    pub fn is_square_attacked(&self, target: Position, by_color: Color) -> bool {
+        !self.attackers_of(target, by_color).is_empty()
+    }
+
+    // Castling isn't part of get_moves_ignoring_check: it can never capture, and
+    // checking the attacked squares from there would recurse through the
+    // opponent's king
+    fn get_castling_moves(&self, pos: Position) -> Vec<Position> {
+        let mut moves = Vec::new();
+        let color = match self.get_piece(pos) {
+            Some(piece) if matches!(piece.chess_piece, ChessPieceType::King(_)) => piece.color,
+            _ => return moves,
+        };
+        let row = match color {
+            Color::White => 0,
+            Color::Black => LAST_ROW,
+        };
+        if pos != Position::try_new(row, 4).unwrap() || self.is_king_in_check(color) != Ok(None) {
+            return moves;
+        }
+
+        for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+            if !self.castling_rights.can_castle(color, side) {
+                continue;
+            }
+            let rook_column = side.get_rook_column();
+            let has_rook = self.squares[row][rook_column]
+                .as_ref()
+                .is_some_and(|piece| {
+                    piece.color == color && matches!(piece.chess_piece, ChessPieceType::Rook(_))
+                });
+            let (low, high) = if rook_column < 4 {
+                (rook_column + 1, 4)
+            } else {
+                (5, rook_column)
+            };
+            let path_empty = (low..high).all(|column| self.squares[row][column].is_none());
+
+            let king_column = side.get_king_destination_column();
+            let passes_attacked = (king_column.min(4)..=king_column.max(4))
+                .filter(|&column| column != 4)
+                .any(|column| {
+                    self.is_square_attacked(Position::try_new(row, column).unwrap(), color.switch())
+                });
+
+            if has_rook && path_empty && !passes_attacked {
+                moves.push(Position::try_new(row, king_column).unwrap());
+            }
+        }
+        moves
+    }
+
+    pub fn get_available_moves(&self, pos: Position) -> Vec<Position> {
         self.get_piece(pos)
             .as_ref()
             .map(|piece| {
-                self.filter_moves_in_check(piece.color, pos, self.get_moves_ignoring_check(pos))
+                let mut moves = self.get_moves_ignoring_check(pos);
+                moves.extend(self.get_castling_moves(pos));
+                self.filter_moves_in_check(piece.color, pos, moves)
             })
-            .unwrap_or_else(Vec::<Position>::new)
+            .unwrap_or_default()
+    }
+
+    // Explains why a piece can't go from one square to the other, Ok when it can
+    pub fn validate_move(&self, from: Position, to: Position) -> Result<()> {
+        let piece = self
+            .get_piece(from)
+            .as_ref()
+            .ok_or(ChessError::NoPieceAtSquare(from))?;
+        if self.get_available_moves(from).contains(&to) {
+            return Ok(());
+        }
+        if self
+            .get_piece(to)
+            .as_ref()
+            .is_some_and(|target| target.color == piece.color)
+        {
+            return Err(ChessError::OwnPieceOnTarget);
+        }
+        if self.get_moves_ignoring_check(from).contains(&to) {
+            return Err(ChessError::KingLeftInCheck);
+        }
+
+        let row_distance = to.get_row().abs_diff(from.get_row());
+        let column_distance = to.get_column().abs_diff(from.get_column());
+        let straight = row_distance == 0 || column_distance == 0;
+        let diagonal = row_distance == column_distance;
+        let along_path = match &piece.chess_piece {
+            ChessPieceType::King(_) => {
+                if CastlingSide::from_king_move(from, to).is_some() {
+                    return Err(ChessError::CastlingNotAllowed);
+                }
+                false
+            }
+            ChessPieceType::Pawn(p) => {
+                column_distance == 0
+                    && (p.move_up(from, 1, piece.color) == Some(to)
+                        || (p.get_starting_row(piece.color) == from.get_row()
+                            && p.move_up(from, 2, piece.color) == Some(to)))
+            }
+            ChessPieceType::Rook(_) => straight,
+            ChessPieceType::Bishop(_) => diagonal,
+            ChessPieceType::Queen(_) => straight || diagonal,
+            ChessPieceType::Knight(_) => false,
+        };
+        if along_path && from != to {
+            Err(ChessError::BlockedPath)
+        } else {
+            Err(ChessError::IllegalDestination)
+        }
+    }
+
+    // Pawn moves to the last row are listed once for every promotion piece
+    pub fn get_all_available_moves(&self, color: Color) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for row in 0..8 {
+            for column in 0..8 {
+                let position = Position::try_new(row, column).unwrap();
+                if let Some(piece) = self.get_piece(position) {
+                    if piece.color == color {
+                        for to in self.get_available_moves(position) {
+                            if self.is_promotion(position, to) {
+                                moves.extend(PromotionPiece::ALL.iter().map(|&p| {
+                                    self.describe_move(Move::with_promotion(position, to, p))
+                                }));
+                            } else {
+                                moves.push(self.describe_move(Move::new(position, to)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    // Fills in the moved and captured pieces and the special move flags
+    fn describe_move(&self, mv: Move) -> Move {
+        let (from, to) = (mv.get_from(), mv.get_to());
+        let piece = match self.get_piece(from) {
+            Some(piece) => *piece,
+            None => return mv,
+        };
+        let castling = match piece.chess_piece {
+            ChessPieceType::King(_) => CastlingSide::from_king_move(from, to),
+            _ => None,
+        };
+        let en_passant = matches!(piece.chess_piece, ChessPieceType::Pawn(_))
+            && self.en_passant == Some(to)
+            && from.get_column() != to.get_column();
+        let captured = if en_passant {
+            self.squares[from.get_row()][to.get_column()]
+        } else {
+            *self.get_piece(to)
+        };
+        mv.with_details(piece, captured, castling, en_passant)
+    }
+
+    pub fn is_promotion(&self, from: Position, to: Position) -> bool {
+        match self.get_piece(from) {
+            Some(piece) => match &piece.chess_piece {
+                ChessPieceType::Pawn(p) => to.get_row() == p.get_promotion_row(piece.color),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    // Unlike move_piece, the move is checked against the legal moves of the
+    // piece's side and promotions replace the pawn. Returns the move with its
+    // details filled in.
+    pub fn make_move(&mut self, mv: &Move) -> Result<Move> {
+        let color = self
+            .get_piece(mv.get_from())
+            .as_ref()
+            .ok_or(ChessError::NoPieceAtSquare(mv.get_from()))?
+            .color;
+        let mv = self
+            .get_all_available_moves(color)
+            .into_iter()
+            .find(|legal| legal == mv)
+            .ok_or_else(|| {
+                self.validate_move(mv.get_from(), mv.get_to())
+                    .err()
+                    .unwrap_or(ChessError::IllegalDestination)
+            })?;
+        self.apply_move(&mv);
+        Ok(mv)
+    }
+
+    // make_move for moves already known to be legal
+    fn apply_move(&mut self, mv: &Move) {
+        let color = match self.get_piece(mv.get_from()) {
+            Some(piece) => piece.color,
+            None => return,
+        };
+        self.move_piece(mv.get_from(), mv.get_to());
+        if let Some(promotion) = mv.get_promotion() {
+            self.set_square(
+                mv.get_to().get_row(),
+                mv.get_to().get_column(),
+                Some(ChessPiece::new(promotion.to_chess_piece_type(), color)),
+            );
+        }
+    }
+
+    // Takes back a move make_move returned. The castling rights and en passant
+    // square from before the move can't be told from the move itself.
+    pub fn unmake_move(
+        &mut self,
+        mv: &Move,
+        castling_rights: CastlingRights,
+        en_passant: Option<Position>,
+    ) {
+        let (from, to) = (mv.get_from(), mv.get_to());
+        // the moved piece is still a pawn for promotions
+        self.set_square(from.get_row(), from.get_column(), mv.get_piece());
+        if mv.is_en_passant() {
+            self.set_square(to.get_row(), to.get_column(), None);
+            self.set_square(from.get_row(), to.get_column(), mv.get_captured());
+        } else {
+            self.set_square(to.get_row(), to.get_column(), mv.get_captured());
+        }
+        if let Some(side) = mv.get_castling() {
+            let row = from.get_row();
+            let rook = self.take_square(row, side.get_rook_destination_column());
+            self.set_square(row, side.get_rook_column(), rook);
+        }
+        self.castling_rights = castling_rights;
+        self.en_passant = en_passant;
     }
 
     pub fn get_moves_ignoring_check(&self, pos: Position) -> Vec<Position> {
@@ -329,14 +773,15 @@ impl Board {
         if let Some(piece) = self.get_piece(pos) {
             match &piece.chess_piece {
                 ChessPieceType::Pawn(p) => {
-                    if let Some(x) = p
+                    let single_push = p
                         .move_up(pos, 1, piece.color)
-                        .filter(|x| self.get_piece(*x).is_none())
-                    {
+                        .filter(|x| self.get_piece(*x).is_none());
+                    if let Some(x) = single_push {
                         available_moves.push(x)
                     }
 
-                    if p.get_starting_row(piece.color) == pos.get_row() {
+                    // the double push can't jump over a piece
+                    if single_push.is_some() && p.get_starting_row(piece.color) == pos.get_row() {
                         if let Some(x) = p
                             .move_up(pos, 2, piece.color)
                             .filter(|x| self.get_piece(*x).is_none())
@@ -345,6 +790,13 @@ impl Board {
                         }
                     }
 
+                    if let Some(target) = self.en_passant.filter(|target| {
+                        target.get_row() == p.get_en_passant_row(piece.color)
+                            && p.get_attacking_squares(pos, piece.color).contains(target)
+                    }) {
+                        available_moves.push(target);
+                    }
+
                     available_moves.extend(
                         p.get_attacking_squares(pos, piece.color)
                             .into_iter()
@@ -365,18 +817,7 @@ impl Board {
                     available_moves.append(&mut self.get_orthogonal_moves(piece, pos));
                 }
                 ChessPieceType::Knight(_k) => {
-                    let available_positions = vec![
-                        (-2, -1),
-                        (-2, 1),
-                        (-1, -2),
-                        (-1, 2),
-                        (1, -2),
-                        (1, 2),
-                        (2, -1),
-                        (2, 1),
-                    ];
-
-                    available_moves = available_positions
+                    available_moves = KNIGHT_OFFSETS
                         .iter()
                         .filter_map(|available_position| pos.try_add(*available_position).ok())
                         .filter(|x| filter_same_color_collision(self.get_piece(*x), piece.color))
@@ -419,26 +860,99 @@ impl Board {
                 }
             }
         }
-        // this is actually an internal error
-        Err(MoveError)
+        Err(ChessError::MissingKing(color))
+    }
+
+    // The king's square if it's in check, boards set up piece by piece may
+    // have no king at all
+    pub fn is_king_in_check(&self, color: Color) -> Result<Option<Position>> {
+        let king_position = self.get_king(color)?;
+        if self.is_square_attacked(king_position, color.switch()) {
+            Ok(Some(king_position))
+        } else {
+            Ok(None)
+        }
     }
 
-    pub fn is_king_in_check(&self, color: Color) -> Option<Position> {
-        let king_position = self.get_king(color).unwrap();
+    pub fn is_checkmate(&self, color: Color) -> bool {
+        self.get_check_info(color).is_some() && self.get_all_available_moves(color).is_empty()
+    }
 
-        for row in self.squares.iter().enumerate() {
-            for column in self.squares[row.0].iter().enumerate() {
-                let position = Position::try_new(row.0, column.0).unwrap();
-                if self
-                    .get_attacking_squares(position, color.switch())
-                    .contains(&king_position)
-                {
-                    return Some(king_position);
+    pub fn is_stalemate(&self, color: Color) -> bool {
+        self.get_check_info(color).is_none() && self.get_all_available_moves(color).is_empty()
+    }
+
+    // Positions where neither side can ever mate: bare kings, a single minor
+    // piece, or bishops that all stand on squares of one color
+    pub fn is_insufficient_material(&self) -> bool {
+        let (white, black) = (self.material(Color::White), self.material(Color::Black));
+        if white.has_major_piece_or_pawn() || black.has_major_piece_or_pawn() {
+            return false;
+        }
+        let mut knights = 0;
+        let mut bishop_square_colors = Vec::new();
+        for (row, squares) in self.squares.iter().enumerate() {
+            for (column, square) in squares.iter().enumerate() {
+                match square.as_ref().map(|piece| &piece.chess_piece) {
+                    None | Some(ChessPieceType::King(_)) => {}
+                    Some(ChessPieceType::Knight(_)) => knights += 1,
+                    Some(ChessPieceType::Bishop(_)) => {
+                        bishop_square_colors.push((row + column) % 2)
+                    }
+                    Some(_) => {}
                 }
             }
         }
+        match (knights, bishop_square_colors.len()) {
+            (0, _) => bishop_square_colors
+                .windows(2)
+                .all(|pair| pair[0] == pair[1]),
+            (1, 0) => true,
+            _ => false,
+        }
+    }
 
-        None
+    pub fn pinned_pieces(&self, color: Color) -> Vec<Pin> {
+        let mut pins = Vec::new();
+        let king = match self.get_king(color) {
+            Ok(king) => king,
+            Err(_) => return pins,
+        };
+        for (squares, diagonal) in get_rays(king) {
+            let mut pinned = None;
+            for (i, &square) in squares.iter().enumerate() {
+                let piece = match self.get_piece(square) {
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                match pinned {
+                    None if piece.color == color => pinned = Some(square),
+                    Some(pinned) if piece.color != color => {
+                        if slides_along(&piece.chess_piece, diagonal) {
+                            pins.push(Pin {
+                                pinned,
+                                pinner: square,
+                                ray: squares[..=i].to_vec(),
+                            });
+                        }
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        pins
+    }
+
+    // None when the king isn't in check, or when there is no king of that color
+    pub fn get_check_info(&self, color: Color) -> Option<CheckInfo> {
+        let king = self.get_king(color).ok()?;
+        let checkers = self.attackers_of(king, color.switch());
+        if checkers.is_empty() {
+            None
+        } else {
+            Some(CheckInfo { king, checkers })
+        }
     }
 }
 
@@ -502,31 +1016,83 @@ impl Pawn {
         }
     }
 
+    fn get_promotion_row(&self, color: Color) -> usize {
+        if color == Color::White {
+            LAST_ROW
+        } else {
+            0
+        }
+    }
+
+    // Row of the square an opposing pawn skipped that this pawn can capture on
+    fn get_en_passant_row(&self, color: Color) -> usize {
+        if color == Color::White {
+            BLACK_PAWN_ROW - 1
+        } else {
+            WHITE_PAWN_ROW + 1
+        }
+    }
+
     fn move_up(&self, pos: Position, distance: usize, color: Color) -> Option<Position> {
         if color == Color::White {
             Position::try_new(pos.get_row() + distance, pos.get_column()).ok()
         } else {
-            Position::try_new(pos.get_row() - distance, pos.get_column()).ok()
+            pos.get_row()
+                .checked_sub(distance)
+                .and_then(|row| Position::try_new(row, pos.get_column()).ok())
         }
     }
 
     fn get_attacking_squares(&self, pos: Position, color: Color) -> Vec<Position> {
         let mut attacking_squares = Vec::new();
         if color == Color::White {
-            if let Some(pos) = pos.get_principal_diagonal_up_squares().get(0) {
+            if let Some(pos) = pos.get_principal_diagonal_up_squares().first() {
                 attacking_squares.push(*pos);
             }
-            if let Some(pos) = pos.get_secondary_diagonal_up_squares().get(0) {
+            if let Some(pos) = pos.get_secondary_diagonal_up_squares().first() {
                 attacking_squares.push(*pos);
             }
         } else {
-            if let Some(pos) = pos.get_principal_diagonal_down_squares().get(0) {
+            if let Some(pos) = pos.get_principal_diagonal_down_squares().first() {
                 attacking_squares.push(*pos);
             }
-            if let Some(pos) = pos.get_secondary_diagonal_down_squares().get(0) {
+            if let Some(pos) = pos.get_secondary_diagonal_down_squares().first() {
                 attacking_squares.push(*pos);
             }
         }
         attacking_squares
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_board_without_a_king_is_never_in_check() {
+        let mut board = Board::new();
+        let square = |name: &str| name.parse::<Position>().unwrap();
+        board
+            .add_piece(
+                ChessPiece::new(ChessPieceType::King(King), Color::White),
+                square("e1"),
+            )
+            .unwrap();
+        board
+            .add_piece(
+                ChessPiece::new(ChessPieceType::Rook(Rook), Color::Black),
+                square("e8"),
+            )
+            .unwrap();
+
+        assert_eq!(board.is_king_in_check(Color::White), Ok(Some(square("e1"))));
+        assert_eq!(
+            board.is_king_in_check(Color::Black),
+            Err(ChessError::MissingKing(Color::Black))
+        );
+        assert!(board.get_check_info(Color::Black).is_none());
+        assert_eq!(board.get_all_available_moves(Color::Black).len(), 14);
+        // the king has to step off the e-file
+        assert_eq!(board.get_available_moves(square("e1")).len(), 4);
+    }
+}
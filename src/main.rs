@@ -1,75 +1,897 @@
-use chess_game::{Board, Color, Position};
-// use chess_game::{Bishop, King, Knight, Pawn, Queen, Rook};
+use chess_game::heatmap::Heatmap;
+use chess_game::notation;
+use chess_game::opening_tree::OpeningTree;
+use chess_game::pgn::{self, ImportedGame, PgnExportOptions, PgnGame, PgnMode, PgnReader};
+use chess_game::problem::{Problem, Soundness, Stipulation};
+use chess_game::progress::ProgressHandle;
+use chess_game::share::{self, SharedLink};
+use chess_game::{Bishop, King, Knight, Pawn, Queen, Rook};
+use chess_game::{
+    CastlingSide, CheckInfo, ChessError, ChessPieceType, Color, Game, GameStatus, GameTree, Move,
+    Position,
+};
 
 use eframe::egui;
-use eframe::egui::Color32;
+use eframe::egui::{Color32, CursorIcon};
 use egui::{Pos2, Rect};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
 fn main() {
-    let gui_board = GuiBoard::new_game();
+    // a shared link may be passed by the desktop URI handler, several links
+    // open the wall view and a PGN file is opened for replay
+    let links = std::env::args().skip(1).collect::<Vec<String>>();
+    let gui_board = match &links[..] {
+        [] => GuiBoard::new_game(),
+        [path] if Path::new(path).is_file() => GuiBoard::from_pgn_file(Path::new(path))
+            .unwrap_or_else(|err| {
+                eprintln!("can't open {}: {}", path, err);
+                GuiBoard::new_game()
+            }),
+        [link] => GuiBoard::from_link(link).unwrap_or_else(|_| {
+            eprintln!("invalid link: {}", link);
+            GuiBoard::new_game()
+        }),
+        links => GuiBoard::wall(links),
+    };
 
-    println!("{}", gui_board.board);
+    println!("{}", gui_board.game.get_board());
 
     let options = eframe::NativeOptions::default();
-    eframe::run_native("Chess game", options, Box::new(|_cc| Box::new(gui_board)));
+    let _ = eframe::run_native("Chess game", options, Box::new(|_cc| Box::new(gui_board)));
 }
 
-const CHESS_SQUARE_SIZE: usize = 40;
+const CHESS_SQUARE_SIZE: f32 = 40.0;
+const MIN_SQUARE_SIZE: f32 = 24.0;
+const MAX_SQUARE_SIZE: f32 = 120.0;
+const LONG_PRESS_SECONDS: f64 = 0.5;
 const LIGHT_BROWN: Color32 = Color32::from_rgb(239, 218, 180);
 const DARK_BROWN: Color32 = Color32::from_rgb(178, 134, 101);
 const BOARD_COLORS: [Color32; 2] = [LIGHT_BROWN, DARK_BROWN];
+const CHECKER_HIGHLIGHT: Color32 = Color32::from_rgb(255, 170, 90);
+const HOVER_HIGHLIGHT: Color32 = Color32::from_rgba_premultiplied(40, 40, 40, 40);
+const WALL_SQUARE_SIZE: f32 = 16.0;
+const PASSED_PAWN_HIGHLIGHT: Color32 = Color32::from_rgb(120, 170, 230);
+const PROMOTION_PATH_HIGHLIGHT: Color32 = Color32::from_rgb(180, 205, 235);
+// the solver searches every defence, longer mates would freeze the window
+const MAX_COMPOSER_MOVES: u32 = 3;
+// how deep into the games the opening explorer goes
+const EXPLORER_PLIES: usize = 20;
+const HEATMAP_COLOR: Color32 = Color32::from_rgb(220, 60, 40);
+const HEATMAP_PIECES: [(&str, ChessPieceType); 6] = [
+    ("pawns", ChessPieceType::Pawn(Pawn)),
+    ("knights", ChessPieceType::Knight(Knight)),
+    ("bishops", ChessPieceType::Bishop(Bishop)),
+    ("rooks", ChessPieceType::Rook(Rook)),
+    ("queens", ChessPieceType::Queen(Queen)),
+    ("king", ChessPieceType::King(King)),
+];
+
+// What gets drawn for a square, only recomputed when the game state changes
+struct SquareView {
+    position: Position,
+    rect: Rect,
+    label: String,
+    fill: Color32,
+    movable: bool,
+}
+
+// Work running on its own thread so the window stays responsive. Dropping
+// the worker cancels the work and throws its result away.
+struct Worker<T> {
+    progress: ProgressHandle,
+    result: mpsc::Receiver<T>,
+}
+
+impl<T: Send + 'static> Worker<T> {
+    fn spawn(work: impl FnOnce(&ProgressHandle) -> T + Send + 'static) -> Self {
+        let progress = ProgressHandle::new();
+        let (sender, result) = mpsc::channel();
+        let worker_progress = progress.clone();
+        thread::spawn(move || {
+            // nobody is waiting any more if the work was cancelled
+            let _ = sender.send(work(&worker_progress));
+        });
+        Worker { progress, result }
+    }
+}
+
+impl<T> Worker<T> {
+    // None until the work is done
+    fn get_result(&self) -> Option<T> {
+        self.result.try_recv().ok()
+    }
+}
+
+impl<T> Drop for Worker<T> {
+    fn drop(&mut self) {
+        self.progress.cancel();
+    }
+}
+
+// A composed problem being solved
+struct ProblemCheck {
+    problem: Problem,
+    // copy the problem's EPD when done instead of reporting the verdict
+    export: bool,
+    worker: Worker<Option<Soundness>>,
+}
 
-#[derive(Default)]
 struct GuiBoard {
-    pub board: Board,
+    pub game: Game,
     prev_clicked_pos: Option<Position>,
     available_positions: Vec<Position>,
-    checked_king: Option<Position>,
-    turn: Color,
+    check_info: Option<CheckInfo>,
+    square_views: Vec<SquareView>,
+    square_size: f32,
+    piece_info: Option<String>,
+    game_over: Option<String>,
+    announce_moves: bool,
+    // last move in words, exposed to screen readers as a live region
+    announcement: Option<String>,
+    // in the editor pieces are picked up and nudged with the arrow keys
+    // without following the rules, the position is checked on leaving it
+    editing: bool,
+    // text of the paste FEN field
+    fen_input: String,
+    // set when the window title needs to change
+    pending_title: Option<String>,
+    // games shown side by side, and the one open in the full board if any
+    wall: Vec<Game>,
+    wall_focus: Option<usize>,
+    // the game being replayed with its variations, `game` is the position
+    // shown and moves played on the board are added as variations
+    replay: Option<GameTree>,
+    // warn before moves that stalemate the opponent in a won position
+    beginner_mode: bool,
+    // the move warned about, playing it again confirms it
+    stalemate_warning: Option<Move>,
+    show_passed_pawns: bool,
+    // the release ending a long press must not count as a click
+    long_pressed: bool,
+    // an unfinished game saved by a previous run, offered until a move is played
+    resume: Option<Game>,
+    // mate-in-N stipulation for the position on the board in composer mode
+    composer_moves: u32,
+    problem_check: Option<ProblemCheck>,
+    // the PGN file opened for replay, the heatmap covers its games
+    database: Option<PathBuf>,
+    // read once, the first time the heatmap is shown
+    database_games: Option<Arc<Vec<ImportedGame>>>,
+    database_loading: Option<Worker<Result<Vec<ImportedGame>, String>>>,
+    show_heatmap: bool,
+    heatmap_piece: ChessPieceType,
+    heatmap_color: Color,
+    // built for the moves played so far, cleared when they change
+    heatmap: Option<Heatmap>,
+    heatmap_building: Option<Worker<Heatmap>>,
+    show_explorer: bool,
+    // covers the whole database, so it's built once
+    opening_tree: Option<OpeningTree>,
+    opening_tree_building: Option<Worker<OpeningTree>>,
+}
+
+impl Default for GuiBoard {
+    fn default() -> Self {
+        GuiBoard {
+            game: Game::default(),
+            prev_clicked_pos: None,
+            available_positions: Vec::new(),
+            check_info: None,
+            square_views: Vec::new(),
+            square_size: CHESS_SQUARE_SIZE,
+            piece_info: None,
+            game_over: None,
+            announce_moves: false,
+            announcement: None,
+            editing: false,
+            fen_input: String::new(),
+            pending_title: None,
+            wall: Vec::new(),
+            wall_focus: None,
+            replay: None,
+            beginner_mode: false,
+            stalemate_warning: None,
+            show_passed_pawns: false,
+            long_pressed: false,
+            resume: None,
+            composer_moves: 2,
+            problem_check: None,
+            database: None,
+            database_games: None,
+            database_loading: None,
+            show_heatmap: false,
+            heatmap_piece: ChessPieceType::Knight(Knight),
+            heatmap_color: Color::White,
+            heatmap: None,
+            heatmap_building: None,
+            show_explorer: false,
+            opening_tree: None,
+            opening_tree_building: None,
+        }
+    }
 }
 
 impl GuiBoard {
     fn new_game() -> Self {
+        let mut gui_board = GuiBoard {
+            resume: autosave_path().and_then(|path| Game::load(&path).ok()),
+            ..Default::default()
+        };
+        gui_board.update_game_state();
+        gui_board
+    }
+
+    fn from_link(link: &str) -> Result<Self, ChessError> {
+        let mut gui_board = GuiBoard {
+            game: game_from_link(link)?,
+            ..Default::default()
+        };
+        gui_board.update_game_state();
+        Ok(gui_board)
+    }
+
+    // Opens the first game of the file, rewound to its starting position
+    fn from_pgn_file(path: &Path) -> Result<Self, ChessError> {
+        let reader = pgn::open_pgn_file(path)?;
+        let mut replay = PgnReader::new(reader, PgnMode::Lenient)
+            .next()
+            .ok_or_else(|| ChessError::ParseError("no games in the file".to_string()))??
+            .to_game_tree()?;
+        replay.go_to(GameTree::ROOT);
+        let mut gui_board = GuiBoard {
+            game: replay.get_game().clone(),
+            replay: Some(replay),
+            database: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        gui_board.update_game_state();
+        Ok(gui_board)
+    }
+
+    // Stepping forward follows the line played last from the position, the
+    // main line unless a variation was just explored
+    fn step_replay(&mut self, forward: bool) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let stepped = if forward {
+            replay.go_forward()
+        } else {
+            replay.go_back()
+        };
+        if stepped {
+            self.game = replay.get_game().clone();
+            self.prev_clicked_pos = None;
+            self.available_positions.clear();
+            self.square_views.clear();
+            self.update_game_state();
+        }
+    }
+
+    // Invalid links are skipped
+    fn wall(links: &[String]) -> Self {
+        let wall = links
+            .iter()
+            .filter_map(|link| {
+                game_from_link(link)
+                    .map_err(|_| eprintln!("invalid link: {}", link))
+                    .ok()
+            })
+            .collect();
         GuiBoard {
-            board: Board::new_game(),
+            wall,
+            pending_title: Some("Chess game".to_string()),
             ..Default::default()
         }
     }
 
+    fn focus_wall_game(&mut self, index: usize) {
+        self.game = self.wall[index].clone();
+        self.wall_focus = Some(index);
+        self.prev_clicked_pos = None;
+        self.available_positions.clear();
+        self.square_views.clear();
+        self.piece_info = None;
+        self.announcement = None;
+        self.update_game_state();
+    }
+
+    // The wall's games are only looked at, nothing done on the full board is
+    // kept
+    fn leave_wall_game(&mut self) {
+        self.wall_focus = None;
+        self.pending_title = Some("Chess game".to_string());
+    }
+
+    fn show_wall(&mut self, ui: &mut egui::Ui) {
+        let board_size = 8.0 * WALL_SQUARE_SIZE;
+        let mut focus = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, game) in self.wall.iter().enumerate() {
+                    let (rect, resp) =
+                        ui.allocate_exact_size(egui::Vec2::splat(board_size), egui::Sense::click());
+                    let painter = ui.painter_at(rect);
+                    for row in 0..8 {
+                        for column in 0..8 {
+                            let position = Position::try_new(row, column).unwrap();
+                            let square = Rect::from_min_size(
+                                rect.min
+                                    + egui::vec2(
+                                        column as f32 * WALL_SQUARE_SIZE,
+                                        (7 - row) as f32 * WALL_SQUARE_SIZE,
+                                    ),
+                                egui::Vec2::splat(WALL_SQUARE_SIZE),
+                            );
+                            painter.rect_filled(square, 0.0, self.get_bg_color(position));
+                            if let Some(piece) = game.get_board().get_piece(position) {
+                                painter.text(
+                                    square.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    piece.draw_piece(),
+                                    egui::FontId::proportional(WALL_SQUARE_SIZE * 0.8),
+                                    Color32::BLACK,
+                                );
+                            }
+                        }
+                    }
+                    let resp = resp
+                        .on_hover_cursor(CursorIcon::PointingHand)
+                        .on_hover_text(format!(
+                            "Move {}, {:?} to move",
+                            game.get_fullmove_number(),
+                            game.get_turn()
+                        ));
+                    if resp.hovered() {
+                        painter.rect_filled(rect, 0.0, HOVER_HIGHLIGHT);
+                    }
+                    if resp.clicked() {
+                        focus = Some(index);
+                    }
+                }
+            });
+        });
+        if let Some(index) = focus {
+            self.focus_wall_game(index);
+        }
+    }
+
+    fn update_game_state(&mut self) {
+        self.heatmap = None;
+        self.heatmap_building = None;
+        self.problem_check = None;
+        let turn = self.game.get_turn();
+        self.check_info = self.game.get_board().get_check_info(turn);
+        let status = self.game.status();
+        self.game_over = match status {
+            GameStatus::Ongoing | GameStatus::Check => None,
+            GameStatus::Checkmate { winner } => Some(format!("Checkmate, {:?} wins", winner)),
+            GameStatus::Stalemate => Some("Stalemate".to_string()),
+            GameStatus::Draw { reason } => Some(format!("Draw: {:?}", reason)),
+        };
+        let title = match status {
+            GameStatus::Ongoing => format!("{:?} to move", turn),
+            GameStatus::Check => format!("Check \u{2014} {:?} to move", turn),
+            GameStatus::Checkmate { winner } => format!("Checkmate \u{2014} {:?} wins", winner),
+            GameStatus::Stalemate => "Stalemate \u{2014} draw".to_string(),
+            GameStatus::Draw { reason } => format!("Draw \u{2014} {:?}", reason),
+        };
+        self.pending_title = Some(format!("{} \u{2014} Chess game", title));
+    }
+
     fn handle_clicked(&mut self, pos: Position) {
+        if self.editing {
+            self.select_for_nudging(pos);
+            return;
+        }
+        if self.game_over.is_some() || self.wall_focus.is_some() {
+            return;
+        }
+        self.square_views.clear();
         if let Some(prev_clicked_pos) = self.prev_clicked_pos {
             println!("prev clicked was: {:?}", prev_clicked_pos);
             if self.available_positions.contains(&pos) {
-                self.board.move_piece(prev_clicked_pos, pos);
-                self.turn = self.turn.switch();
-
-                self.checked_king = self.board.is_king_in_check(self.turn);
+                // There is no piece picker yet, pawns promote to the first
+                // allowed piece, a queen unless the game restricts it
+                let mv = if self.game.get_board().is_promotion(prev_clicked_pos, pos) {
+                    let promotion = self.game.get_allowed_promotions()[0];
+                    Move::with_promotion(prev_clicked_pos, pos, promotion)
+                } else {
+                    Move::new(prev_clicked_pos, pos)
+                };
+                if self.beginner_mode
+                    && self.stalemate_warning != Some(mv)
+                    && self.is_stalemate_trap(&mv)
+                {
+                    // the selection stays so the move can be confirmed
+                    self.stalemate_warning = Some(mv);
+                    self.piece_info = Some(
+                        "This stalemates the opponent and throws away the win, play it again to confirm"
+                            .to_string(),
+                    );
+                    return;
+                }
+                self.stalemate_warning = None;
+                // spoken from the position before the move
+                let announcement = self
+                    .announce_moves
+                    .then(|| notation::move_to_speech(self.game.get_board(), &mv).ok())
+                    .flatten();
+                if self.game.make_move(&mv).is_ok() {
+                    self.announcement = announcement;
+                    if let Some(replay) = &mut self.replay {
+                        // the tree is at the same position, so the move is legal there too
+                        replay.make_move(&mv).unwrap();
+                    }
+                    self.update_game_state();
+                    self.autosave();
+                }
             }
             self.prev_clicked_pos = None;
             self.available_positions.clear();
-        } else if let Some(ref piece) = self.board.get_piece(pos) {
-            if piece.color == self.turn {
+        } else if let Some(ref piece) = self.game.get_board().get_piece(pos) {
+            if piece.color == self.game.get_turn() {
                 self.prev_clicked_pos = Some(pos);
-                self.available_positions = self.board.get_available_moves(pos);
+                self.available_positions = self.game.get_board().get_available_moves(pos);
+            }
+        }
+    }
+
+    // Only games played from scratch or from a link are saved, a finished
+    // game has nothing to resume
+    fn autosave(&mut self) {
+        self.resume = None;
+        if self.replay.is_some() || !self.wall.is_empty() {
+            return;
+        }
+        let Some(path) = autosave_path() else {
+            return;
+        };
+        let result = if self.game_over.is_some() {
+            match fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            }
+        } else {
+            path.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(ChessError::from)
+                .and_then(|_| self.game.save(&path))
+        };
+        if let Err(err) = result {
+            eprintln!("can't autosave to {}: {}", path.display(), err);
+        }
+    }
+
+    fn resume_saved_game(&mut self) {
+        if let Some(game) = self.resume.take() {
+            self.game = game;
+            self.prev_clicked_pos = None;
+            self.available_positions.clear();
+            self.square_views.clear();
+            self.update_game_state();
+        }
+    }
+
+    // Any piece can be picked up in the editor, clicking it again puts it down
+    fn select_for_nudging(&mut self, pos: Position) {
+        self.square_views.clear();
+        self.prev_clicked_pos = match self.game.get_board().get_piece(pos) {
+            Some(_) if self.prev_clicked_pos != Some(pos) => Some(pos),
+            _ => None,
+        };
+    }
+
+    // Moves the picked up piece one square, onto empty squares only. Castling
+    // rights go with a king or rook that leaves its starting square.
+    fn nudge_piece(&mut self, row_step: isize, column_step: isize) {
+        let Some(from) = self.prev_clicked_pos else {
+            return;
+        };
+        let Some(to) = from
+            .get_row()
+            .checked_add_signed(row_step)
+            .zip(from.get_column().checked_add_signed(column_step))
+            .and_then(|(row, column)| Position::try_new(row, column).ok())
+        else {
+            return;
+        };
+        let mut board = self.game.get_board().clone();
+        if board.get_piece(to).is_some() {
+            return;
+        }
+        if let Some(piece) = board.remove_piece(from) {
+            board.add_piece(piece, to).unwrap();
+        }
+        board.en_passant = None;
+        for (color, row) in [(Color::White, 0), (Color::Black, 7)] {
+            for side in [CastlingSide::KingSide, CastlingSide::QueenSide] {
+                let home = |column: usize| {
+                    board
+                        .get_piece(Position::try_new(row, column).unwrap())
+                        .filter(|piece| piece.color == color)
+                        .map(|piece| piece.get_type())
+                };
+                let in_place = matches!(home(4), Some(ChessPieceType::King(_)))
+                    && matches!(home(side.get_rook_column()), Some(ChessPieceType::Rook(_)));
+                if !in_place {
+                    board.castling_rights.set(color, side, false);
+                }
+            }
+        }
+        self.game = Game::from_position(board, self.game.get_turn());
+        self.prev_clicked_pos = Some(to);
+        self.square_views.clear();
+        self.update_game_state();
+    }
+
+    // Leaving the editor starts a game from the edited position, which has to
+    // be one a FEN could describe
+    fn toggle_editor(&mut self) {
+        if self.editing {
+            match Game::from_fen(&self.game.to_fen()) {
+                Ok(game) => {
+                    self.game = game;
+                    self.editing = false;
+                    self.piece_info = None;
+                }
+                Err(err) => {
+                    self.piece_info = Some(format!("Can't play from this position: {}", err));
+                    return;
+                }
+            }
+        } else {
+            // the edited position isn't part of the game being replayed
+            self.editing = true;
+            self.replay = None;
+        }
+        self.prev_clicked_pos = None;
+        self.available_positions.clear();
+        self.square_views.clear();
+        self.update_game_state();
+    }
+
+    // A pasted position starts a new game, it leaves any replay
+    fn load_fen(&mut self) {
+        match Game::from_fen(self.fen_input.trim()) {
+            Ok(game) => {
+                self.game = game;
+                self.replay = None;
+                self.fen_input.clear();
+                self.piece_info = None;
+                self.prev_clicked_pos = None;
+                self.available_positions.clear();
+                self.square_views.clear();
+                self.update_game_state();
+            }
+            Err(err) => self.piece_info = Some(format!("Invalid FEN: {}", err)),
+        }
+    }
+
+    // The database is read once, the first time one of its views is shown
+    fn update_database(&mut self) {
+        if let Some(loaded) = self.database_loading.as_ref().and_then(Worker::get_result) {
+            self.database_loading = None;
+            match loaded {
+                Ok(games) => self.database_games = Some(Arc::new(games)),
+                Err(message) => {
+                    self.piece_info = Some(message);
+                    self.show_heatmap = false;
+                    self.show_explorer = false;
+                }
+            }
+        }
+        let wanted = self.show_heatmap || self.show_explorer;
+        if wanted && self.database_games.is_none() && self.database_loading.is_none() {
+            if let Some(path) = &self.database {
+                let path = path.clone();
+                self.database_loading = Some(Worker::spawn(move |progress| {
+                    read_database(&path, progress)
+                }));
+            }
+        }
+        self.update_heatmap();
+        self.update_explorer();
+    }
+
+    // Built from the database games that follow the moves played so far,
+    // again whenever those change
+    fn update_heatmap(&mut self) {
+        if let Some(heatmap) = self.heatmap_building.as_ref().and_then(Worker::get_result) {
+            self.heatmap_building = None;
+            self.heatmap = Some(heatmap);
+            self.square_views.clear();
+        }
+        if !self.show_heatmap || self.heatmap.is_some() || self.heatmap_building.is_some() {
+            return;
+        }
+        let Some(games) = &self.database_games else {
+            return;
+        };
+        let games = Arc::clone(games);
+        let mut heatmap = Heatmap::from_game(self.heatmap_piece, self.heatmap_color, &self.game);
+        self.heatmap_building = Some(Worker::spawn(move |progress| {
+            progress.set_total(games.len() as u64);
+            for game in games.iter() {
+                if progress.is_cancelled() {
+                    break;
+                }
+                heatmap.add_game(game);
+                progress.advance(1);
+            }
+            heatmap
+        }));
+    }
+
+    fn update_explorer(&mut self) {
+        if let Some(tree) = self
+            .opening_tree_building
+            .as_ref()
+            .and_then(Worker::get_result)
+        {
+            self.opening_tree_building = None;
+            self.opening_tree = Some(tree);
+        }
+        if !self.show_explorer
+            || self.opening_tree.is_some()
+            || self.opening_tree_building.is_some()
+        {
+            return;
+        }
+        let Some(games) = &self.database_games else {
+            return;
+        };
+        let games = Arc::clone(games);
+        self.opening_tree_building = Some(Worker::spawn(move |progress| {
+            let mut tree = OpeningTree::new(EXPLORER_PLIES);
+            progress.set_total(games.len() as u64);
+            for game in games.iter() {
+                if progress.is_cancelled() {
+                    break;
+                }
+                tree.add_game(game);
+                progress.advance(1);
+            }
+            tree
+        }));
+    }
+
+    // How the database's games went on after the moves played so far
+    fn explorer_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_explorer;
+        egui::Window::new("Opening explorer")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let Some(tree) = &self.opening_tree else {
+                    ui.label("Reading games\u{2026}");
+                    return;
+                };
+                if self.game.get_starting_fen() != Game::new().get_starting_fen() {
+                    ui.label("Only games from the initial position are explored");
+                    return;
+                }
+                let moves = tree.get_moves(self.game.get_moves());
+                if moves.is_empty() {
+                    ui.label("No games went on from here");
+                    return;
+                }
+                let board = self.game.get_board();
+                egui::Grid::new("explorer moves")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for heading in ["Move", "Games", "White score", "Rating"] {
+                            ui.strong(heading);
+                        }
+                        ui.end_row();
+                        for (mv, stats) in moves {
+                            let san = notation::move_to_san(board, &mv)
+                                .unwrap_or_else(|_| mv.to_string());
+                            let score = stats
+                                .get_white_score()
+                                .map_or("-".to_string(), |score| format!("{:.0}%", score * 100.0));
+                            let rating = stats
+                                .get_average_rating()
+                                .map_or("-".to_string(), |rating| rating.to_string());
+                            ui.label(san);
+                            ui.label(stats.games.to_string());
+                            ui.label(score);
+                            ui.label(rating);
+                            ui.end_row();
+                        }
+                    });
+            });
+        self.show_explorer = open;
+    }
+
+    fn get_problem(&self) -> Problem {
+        Problem::new(self.game.clone(), Stipulation::MateIn(self.composer_moves))
+    }
+
+    // Starting another check cancels the one in progress
+    fn start_problem_check(&mut self, export: bool) {
+        let problem = self.get_problem();
+        let solved = problem.clone();
+        self.problem_check = Some(ProblemCheck {
+            problem,
+            export,
+            worker: Worker::spawn(move |progress| solved.check_soundness_with_progress(progress)),
+        });
+    }
+
+    // Reports the verdict once the check is done, returns the EPD to copy if
+    // it was an export
+    fn poll_problem_check(&mut self) -> Option<String> {
+        let result = self.problem_check.as_ref()?.worker.get_result()?;
+        let check = self.problem_check.take()?;
+        let soundness = result?;
+        if check.export {
+            let record = check.problem.soundness_to_epd(&soundness);
+            if record.is_none() {
+                self.piece_info = Some("Only sound problems can be exported".to_string());
             }
+            return record.map(|record| record.to_string());
         }
+        let board = check.problem.get_game().get_board();
+        let san = |mv: &Move| notation::move_to_san(board, mv).unwrap_or_else(|_| mv.to_string());
+        let Stipulation::MateIn(moves) = check.problem.get_stipulation();
+        self.piece_info = Some(match soundness {
+            Soundness::Sound(key) => format!("Sound, the key is {}", san(&key)),
+            Soundness::NoSolution => format!("No mate in {}", moves),
+            Soundness::Cooked(keys) => format!(
+                "Cooked, solved by {}",
+                keys.iter().map(san).collect::<Vec<String>>().join(", ")
+            ),
+        });
+        None
+    }
+
+    fn is_stalemate_trap(&self, mv: &Move) -> bool {
+        let board = self.game.get_board();
+        let turn = self.game.get_turn();
+        board.material(turn).get_value() > board.material(turn.switch()).get_value()
+            && self.game.is_stalemating_move(mv)
     }
 
     fn get_ui_pos(&self, pos: Position) -> Rect {
+        let size = self.square_size;
         Rect {
             min: Pos2 {
-                x: (pos.get_column() * CHESS_SQUARE_SIZE) as f32,
-                y: ((7 - pos.get_row()) * CHESS_SQUARE_SIZE) as f32,
+                x: pos.get_column() as f32 * size,
+                y: (7 - pos.get_row()) as f32 * size,
             },
             max: Pos2 {
-                x: ((pos.get_column() * CHESS_SQUARE_SIZE) + CHESS_SQUARE_SIZE) as f32,
-                y: (((7 - pos.get_row()) * CHESS_SQUARE_SIZE) + CHESS_SQUARE_SIZE) as f32,
+                x: pos.get_column() as f32 * size + size,
+                y: (7 - pos.get_row()) as f32 * size + size,
             },
         }
     }
 
+    fn describe_square(&self, pos: Position) -> String {
+        match self.game.get_board().get_piece(pos) {
+            Some(piece) => format!(
+                "{} {:?} on {}, {} legal moves",
+                piece.draw_piece(),
+                piece.color,
+                pos,
+                self.game.get_board().get_available_moves(pos).len()
+            ),
+            None => format!("{} is empty", pos),
+        }
+    }
+
+    fn refresh_square_views(&mut self) {
+        self.square_views.clear();
+        let heatmap = self.heatmap.as_ref().filter(|_| self.show_heatmap);
+        let board = self.game.get_board();
+        let (passed_pawns, promotion_paths) = if self.show_passed_pawns {
+            let pawns = [Color::White, Color::Black]
+                .into_iter()
+                .flat_map(|color| board.get_passed_pawns(color))
+                .collect::<Vec<Position>>();
+            let paths = pawns
+                .iter()
+                .flat_map(|&pawn| board.get_promotion_path(pawn))
+                .collect::<Vec<Position>>();
+            (pawns, paths)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        for row in 0..8 {
+            for column in 0..8 {
+                let position = Position::try_new(row, column).unwrap();
+                let label = match self.game.get_board().get_piece(position) {
+                    Some(piece) => piece.draw_piece(),
+                    None => ' ',
+                }
+                .to_string();
+
+                let mut fill = self.get_bg_color(position);
+                if passed_pawns.contains(&position) {
+                    fill = PASSED_PAWN_HIGHLIGHT;
+                } else if promotion_paths.contains(&position) {
+                    fill = PROMOTION_PATH_HIGHLIGHT;
+                } else if let Some(heatmap) = heatmap {
+                    fill = blend(fill, HEATMAP_COLOR, heatmap.get_intensity(position));
+                }
+                if self.available_positions.contains(&position) {
+                    fill = Color32::LIGHT_GREEN;
+                }
+                if let Some(check_info) = &self.check_info {
+                    if check_info.king == position {
+                        fill = Color32::LIGHT_RED;
+                    } else if check_info.checkers.contains(&position) {
+                        fill = CHECKER_HIGHLIGHT;
+                    }
+                }
+                if self.prev_clicked_pos == Some(position) {
+                    fill = Color32::GREEN;
+                }
+
+                let movable = self.wall_focus.is_none()
+                    && self
+                        .game
+                        .get_board()
+                        .get_piece(position)
+                        .as_ref()
+                        .is_some_and(|piece| piece.color == self.game.get_turn())
+                    && !self
+                        .game
+                        .get_board()
+                        .get_available_moves(position)
+                        .is_empty();
+
+                self.square_views.push(SquareView {
+                    position,
+                    rect: self.get_ui_pos(position),
+                    label,
+                    fill,
+                    movable,
+                });
+            }
+        }
+    }
+
+    // Outlines the square of each passed pawn, green when the defending king
+    // is inside it and catches the pawn, red when it doesn't
+    fn paint_pawn_squares(&self, painter: &egui::Painter) {
+        let board = self.game.get_board();
+        for color in [Color::White, Color::Black] {
+            for pawn in board.get_passed_pawns(color) {
+                let Some(distance) = board.get_promotion_distance(pawn) else {
+                    continue;
+                };
+                let promotion_row = match color {
+                    Color::White => 7,
+                    Color::Black => 0,
+                };
+                let corner = |row: usize, column: usize| {
+                    self.get_ui_pos(Position::try_new(row, column).unwrap())
+                };
+                let first = corner(
+                    pawn.get_row().max(promotion_row),
+                    pawn.get_column().saturating_sub(distance),
+                );
+                let last = corner(
+                    pawn.get_row().min(promotion_row),
+                    (pawn.get_column() + distance).min(7),
+                );
+                let stroke_color = if board.is_king_in_square(pawn, self.game.get_turn()) {
+                    Color32::DARK_GREEN
+                } else {
+                    Color32::RED
+                };
+                painter.rect_stroke(first.union(last), 0.0, egui::Stroke::new(2.0, stroke_color));
+            }
+        }
+    }
+
     fn get_bg_color(&self, pos: Position) -> Color32 {
-        if pos.get_row() % 2 == 0 {
+        if pos.get_row().is_multiple_of(2) {
             BOARD_COLORS[pos.get_column() % 2]
         } else {
             BOARD_COLORS[1 - pos.get_column() % 2]
@@ -79,44 +901,663 @@ impl GuiBoard {
 
 impl eframe::App for GuiBoard {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(title) = self.pending_title.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+        }
+        if let Some(epd) = self.poll_problem_check() {
+            ctx.output_mut(|output| output.copied_text = epd);
+        }
+        self.update_database();
+        let working = self.problem_check.is_some()
+            || self.database_loading.is_some()
+            || self.heatmap_building.is_some()
+            || self.opening_tree_building.is_some();
+        if working {
+            // workers don't wake the window up when they're done
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        if !self.wall.is_empty() && self.wall_focus.is_none() {
+            egui::CentralPanel::default().show(ctx, |ui| self.show_wall(ui));
+            return;
+        }
+        if self.show_explorer {
+            self.explorer_window(ctx);
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
-            for row in 0..8 {
-                for column in 0..8 {
-                    let mut button = egui::Button::new(
-                        match self.board.squares[row][column] {
-                            Some(ref square) => square.draw_piece(),
-                            _ => ' ',
-                        }
-                        .to_string(),
-                    );
-                    let current_position = Position::try_new(row, column).unwrap();
-                    let mut bg_color = self.get_bg_color(current_position);
+            if ctx.input(|i| i.pointer.any_pressed()) {
+                self.long_pressed = false;
+            }
 
-                    if self.available_positions.contains(&current_position) {
-                        bg_color = Color32::LIGHT_GREEN;
-                    }
+            // pinch gestures on touchscreens, ctrl + scroll on desktop
+            let zoom = ctx.input(|i| i.zoom_delta());
+            if zoom != 1.0 {
+                self.square_size =
+                    (self.square_size * zoom).clamp(MIN_SQUARE_SIZE, MAX_SQUARE_SIZE);
+                self.square_views.clear();
+            }
 
-                    if let Some(check) = self.checked_king {
-                        if check == current_position {
-                            bg_color = Color32::LIGHT_RED;
-                        }
-                    }
+            if self.square_views.is_empty() {
+                self.refresh_square_views();
+            }
 
-                    if let Some(clicked_pos) = self.prev_clicked_pos {
-                        if clicked_pos == (current_position) {
-                            bg_color = Color32::GREEN;
+            let mut clicked_pos = None;
+            let mut info_pos = None;
+            for square in &self.square_views {
+                let label = egui::RichText::new(square.label.as_str()).size(self.square_size * 0.6);
+                let button = egui::Button::new(label).fill(square.fill);
+                let mut resp = ui.put(square.rect, button);
+                if resp.hovered() {
+                    ui.painter().rect_filled(square.rect, 0.0, HOVER_HIGHLIGHT);
+                    let cursor = match self.prev_clicked_pos {
+                        None if square.movable => CursorIcon::Grab,
+                        None => CursorIcon::Default,
+                        Some(_) if self.available_positions.contains(&square.position) => {
+                            CursorIcon::PointingHand
                         }
+                        Some(_) => CursorIcon::NotAllowed,
+                    };
+                    resp = resp.on_hover_cursor(cursor);
+                }
+                if resp.clicked() {
+                    clicked_pos = Some(square.position);
+                }
+                if resp.secondary_clicked() {
+                    info_pos = Some(square.position);
+                }
+                if resp.is_pointer_button_down_on() {
+                    let pressed_for = ctx.input(|i| {
+                        i.pointer
+                            .press_start_time()
+                            .map_or(0.0, |start| i.time - start)
+                    });
+                    if pressed_for >= LONG_PRESS_SECONDS {
+                        info_pos = Some(square.position);
+                        self.long_pressed = true;
+                    } else {
+                        // holding still produces no input events to repaint on
+                        ctx.request_repaint_after(Duration::from_secs_f64(
+                            LONG_PRESS_SECONDS - pressed_for,
+                        ));
                     }
+                }
+            }
+            if self.show_passed_pawns {
+                self.paint_pawn_squares(ui.painter());
+            }
+            if let Some(pos) = info_pos {
+                self.piece_info = Some(self.describe_square(pos));
+            } else if let Some(pos) = clicked_pos {
+                if !self.long_pressed {
+                    self.piece_info = None;
+                    self.handle_clicked(pos);
+                    println!("clicked {} {}", pos.get_row(), pos.get_column());
+                }
+            }
 
-                    button = button.fill(bg_color);
+            let share_button = egui::Button::new("Copy position link");
+            let board_bottom = 8.0 * self.square_size;
+            let share_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 10.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 40.0,
+                },
+            };
+            if ui.put(share_rect, share_button).clicked() {
+                let link = share::encode_position(self.game.get_board(), self.game.get_turn());
+                ui.output_mut(|output| output.copied_text = link);
+            }
 
-                    let resp = ui.put(self.get_ui_pos(current_position), button);
-                    if resp.clicked() {
-                        self.handle_clicked(current_position);
-                        println!("clicked {} {}", row, column);
+            if let Some(ref game_over) = self.game_over {
+                let status_rect = Rect {
+                    min: Pos2 {
+                        x: 170.0,
+                        y: board_bottom + 10.0,
+                    },
+                    max: Pos2 {
+                        x: 8.0 * self.square_size,
+                        y: board_bottom + 40.0,
+                    },
+                };
+                ui.put(status_rect, egui::Label::new(game_over.as_str()));
+            } else if let Some(reason) = self.game.get_claimable_draw() {
+                let claim_rect = Rect {
+                    min: Pos2 {
+                        x: 170.0,
+                        y: board_bottom + 10.0,
+                    },
+                    max: Pos2 {
+                        x: 330.0,
+                        y: board_bottom + 40.0,
+                    },
+                };
+                let claim_button = ui
+                    .put(claim_rect, egui::Button::new("Claim draw"))
+                    .on_hover_text(format!("{:?}", reason));
+                if claim_button.clicked() && self.game.claim_draw().is_some() {
+                    self.prev_clicked_pos = None;
+                    self.available_positions.clear();
+                    self.square_views.clear();
+                    self.update_game_state();
+                    self.autosave();
+                }
+            }
+
+            let announce_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 90.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 120.0,
+                },
+            };
+            ui.put(
+                announce_rect,
+                egui::Checkbox::new(&mut self.announce_moves, "Announce moves"),
+            );
+            if let Some(ref announcement) = self.announcement {
+                let announcement_rect = Rect {
+                    min: Pos2 {
+                        x: 170.0,
+                        y: board_bottom + 90.0,
+                    },
+                    max: Pos2 {
+                        x: 8.0 * self.square_size,
+                        y: board_bottom + 120.0,
+                    },
+                };
+                let resp = ui.put(announcement_rect, egui::Label::new(announcement.as_str()));
+                ctx.accesskit_node_builder(resp.id, |builder| {
+                    builder.set_live(egui::accesskit::Live::Polite);
+                });
+            }
+
+            let pgn_rect = Rect {
+                min: Pos2 {
+                    x: 170.0,
+                    y: board_bottom + 130.0,
+                },
+                max: Pos2 {
+                    x: 330.0,
+                    y: board_bottom + 160.0,
+                },
+            };
+            if ui.put(pgn_rect, egui::Button::new("Copy PGN")).clicked() {
+                let pgn_game = match &self.replay {
+                    Some(replay) => PgnGame::from_game_tree(replay),
+                    None => PgnGame::from_game(&self.game),
+                };
+                let pgn = pgn_game.to_pgn(&PgnExportOptions::default());
+                ui.output_mut(|output| output.copied_text = pgn);
+            }
+
+            if self.wall_focus.is_some() {
+                let back_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 130.0,
+                    },
+                    max: Pos2 {
+                        x: 160.0,
+                        y: board_bottom + 160.0,
+                    },
+                };
+                if ui
+                    .put(back_rect, egui::Button::new("Back to wall"))
+                    .clicked()
+                {
+                    self.leave_wall_game();
+                }
+            }
+
+            if let Some(replay) = &self.replay {
+                let mut text = format!(
+                    "Ply {} of {}",
+                    self.game.get_ply_count(),
+                    replay.get_main_line().len()
+                );
+                if !replay.is_main_line(replay.get_current()) {
+                    text.push_str(", in a variation");
+                }
+                let back_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 170.0,
+                    },
+                    max: Pos2 {
+                        x: 40.0,
+                        y: board_bottom + 200.0,
+                    },
+                };
+                let forward_rect = Rect {
+                    min: Pos2 {
+                        x: 50.0,
+                        y: board_bottom + 170.0,
+                    },
+                    max: Pos2 {
+                        x: 90.0,
+                        y: board_bottom + 200.0,
+                    },
+                };
+                let ply_rect = Rect {
+                    min: Pos2 {
+                        x: 100.0,
+                        y: board_bottom + 170.0,
+                    },
+                    max: Pos2 {
+                        x: 8.0 * self.square_size,
+                        y: board_bottom + 200.0,
+                    },
+                };
+                ui.put(ply_rect, egui::Label::new(text));
+                // the arrow keys move the cursor while the FEN field is focused
+                let arrow_pressed =
+                    |key| !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(key));
+                let back = ui.put(back_rect, egui::Button::new("\u{25C0}")).clicked()
+                    || arrow_pressed(egui::Key::ArrowLeft);
+                let forward = ui
+                    .put(forward_rect, egui::Button::new("\u{25B6}"))
+                    .clicked()
+                    || arrow_pressed(egui::Key::ArrowRight);
+                if back {
+                    self.step_replay(false);
+                } else if forward {
+                    self.step_replay(true);
+                }
+            }
+
+            if self.resume.is_some() {
+                let resume_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 170.0,
+                    },
+                    max: Pos2 {
+                        x: 160.0,
+                        y: board_bottom + 200.0,
+                    },
+                };
+                if ui
+                    .put(resume_rect, egui::Button::new("Resume saved game"))
+                    .clicked()
+                {
+                    self.resume_saved_game();
+                }
+            }
+
+            let beginner_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 210.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 240.0,
+                },
+            };
+            ui.put(
+                beginner_rect,
+                egui::Checkbox::new(&mut self.beginner_mode, "Beginner mode"),
+            );
+            let passed_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 250.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 280.0,
+                },
+            };
+            let passed_pawns = egui::Checkbox::new(&mut self.show_passed_pawns, "Passed pawns");
+            if ui.put(passed_rect, passed_pawns).changed() {
+                self.square_views.clear();
+            }
+            let editor_rect = Rect {
+                min: Pos2 {
+                    x: 170.0,
+                    y: board_bottom + 250.0,
+                },
+                max: Pos2 {
+                    x: 330.0,
+                    y: board_bottom + 280.0,
+                },
+            };
+            let editor_label = if self.editing {
+                "Done editing"
+            } else {
+                "Edit position"
+            };
+            if ui
+                .put(editor_rect, egui::Button::new(editor_label))
+                .clicked()
+            {
+                self.toggle_editor();
+            }
+            if self.editing && !ctx.wants_keyboard_input() {
+                for (key, row_step, column_step) in [
+                    (egui::Key::ArrowUp, 1, 0),
+                    (egui::Key::ArrowDown, -1, 0),
+                    (egui::Key::ArrowLeft, 0, -1),
+                    (egui::Key::ArrowRight, 0, 1),
+                ] {
+                    if ctx.input(|i| i.key_pressed(key)) {
+                        self.nudge_piece(row_step, column_step);
                     }
                 }
             }
+            let material_rect = Rect {
+                min: Pos2 {
+                    x: 170.0,
+                    y: board_bottom + 210.0,
+                },
+                max: Pos2 {
+                    x: 8.0 * self.square_size,
+                    y: board_bottom + 240.0,
+                },
+            };
+            let material = format!("Material: {}", self.game.get_board().material_imbalance());
+            ui.put(material_rect, egui::Label::new(material));
+
+            let copy_fen_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 290.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 320.0,
+                },
+            };
+            if ui
+                .put(copy_fen_rect, egui::Button::new("Copy FEN"))
+                .clicked()
+            {
+                let fen = self.game.to_fen();
+                ui.output_mut(|output| output.copied_text = fen);
+            }
+            let fen_input_rect = Rect {
+                min: Pos2 {
+                    x: 170.0,
+                    y: board_bottom + 290.0,
+                },
+                max: Pos2 {
+                    x: 330.0,
+                    y: board_bottom + 320.0,
+                },
+            };
+            let fen_input = egui::TextEdit::singleline(&mut self.fen_input).hint_text("Paste FEN");
+            let fen_entered = ui.put(fen_input_rect, fen_input).lost_focus()
+                && ctx.input(|i| i.key_pressed(egui::Key::Enter));
+            let paste_fen_rect = Rect {
+                min: Pos2 {
+                    x: 340.0,
+                    y: board_bottom + 290.0,
+                },
+                max: Pos2 {
+                    x: 500.0,
+                    y: board_bottom + 320.0,
+                },
+            };
+            if ui
+                .put(paste_fen_rect, egui::Button::new("Paste FEN"))
+                .clicked()
+                || fen_entered
+            {
+                self.load_fen();
+            }
+
+            let composer_rect = Rect {
+                min: Pos2 {
+                    x: 0.0,
+                    y: board_bottom + 330.0,
+                },
+                max: Pos2 {
+                    x: 160.0,
+                    y: board_bottom + 360.0,
+                },
+            };
+            let composer_moves = egui::DragValue::new(&mut self.composer_moves)
+                .clamp_range(1..=MAX_COMPOSER_MOVES)
+                .prefix("Mate in ");
+            ui.put(composer_rect, composer_moves);
+            let check_rect = Rect {
+                min: Pos2 {
+                    x: 170.0,
+                    y: board_bottom + 330.0,
+                },
+                max: Pos2 {
+                    x: 330.0,
+                    y: board_bottom + 360.0,
+                },
+            };
+            let export_rect = Rect {
+                min: Pos2 {
+                    x: 340.0,
+                    y: board_bottom + 330.0,
+                },
+                max: Pos2 {
+                    x: 500.0,
+                    y: board_bottom + 360.0,
+                },
+            };
+            if let Some(check) = &self.problem_check {
+                let solved = check.worker.progress.get_fraction().unwrap_or(0.0);
+                ui.put(
+                    check_rect,
+                    egui::ProgressBar::new(solved).text("Solving\u{2026}"),
+                );
+                if ui.put(export_rect, egui::Button::new("Cancel")).clicked() {
+                    self.problem_check = None;
+                }
+            } else {
+                if ui
+                    .put(check_rect, egui::Button::new("Check problem"))
+                    .clicked()
+                {
+                    self.start_problem_check(false);
+                }
+                if ui
+                    .put(export_rect, egui::Button::new("Copy problem EPD"))
+                    .clicked()
+                {
+                    self.start_problem_check(true);
+                }
+            }
+
+            if self.database.is_some() {
+                let heatmap_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 370.0,
+                    },
+                    max: Pos2 {
+                        x: 160.0,
+                        y: board_bottom + 400.0,
+                    },
+                };
+                let show_heatmap = egui::Checkbox::new(&mut self.show_heatmap, "Heatmap");
+                if ui.put(heatmap_rect, show_heatmap).changed() {
+                    self.square_views.clear();
+                }
+                let pieces_rect = Rect {
+                    min: Pos2 {
+                        x: 170.0,
+                        y: board_bottom + 370.0,
+                    },
+                    max: Pos2 {
+                        x: 330.0,
+                        y: board_bottom + 400.0,
+                    },
+                };
+                let describe = |color: Color, piece: ChessPieceType| {
+                    let (name, _) = HEATMAP_PIECES.iter().find(|(_, p)| *p == piece).unwrap();
+                    format!("{:?} {}", color, name)
+                };
+                let selected = (self.heatmap_color, self.heatmap_piece);
+                let mut choice = selected;
+                ui.allocate_ui_at_rect(pieces_rect, |ui| {
+                    egui::ComboBox::from_id_source("heatmap pieces")
+                        .selected_text(describe(choice.0, choice.1))
+                        .show_ui(ui, |ui| {
+                            for color in [Color::White, Color::Black] {
+                                for (_, piece) in HEATMAP_PIECES {
+                                    ui.selectable_value(
+                                        &mut choice,
+                                        (color, piece),
+                                        describe(color, piece),
+                                    );
+                                }
+                            }
+                        });
+                });
+                if choice != selected {
+                    (self.heatmap_color, self.heatmap_piece) = choice;
+                    self.heatmap = None;
+                    self.heatmap_building = None;
+                    self.square_views.clear();
+                }
+                let games_rect = Rect {
+                    min: Pos2 {
+                        x: 340.0,
+                        y: board_bottom + 370.0,
+                    },
+                    max: Pos2 {
+                        x: 8.0 * self.square_size,
+                        y: board_bottom + 400.0,
+                    },
+                };
+                let working = [
+                    (
+                        self.database_loading.as_ref().map(|w| &w.progress),
+                        "Reading games",
+                    ),
+                    (
+                        self.heatmap_building.as_ref().map(|w| &w.progress),
+                        "Counting",
+                    ),
+                    (
+                        self.opening_tree_building.as_ref().map(|w| &w.progress),
+                        "Building the opening tree",
+                    ),
+                ]
+                .into_iter()
+                .find_map(|(progress, task)| Some((progress?, task)));
+                if let Some((progress, task)) = working {
+                    // compressed files don't say how much text they hold
+                    let bar = match progress.get_fraction() {
+                        Some(fraction) => egui::ProgressBar::new(fraction).text(format!(
+                            "{}\u{2026} {:.0}%",
+                            task,
+                            fraction * 100.0
+                        )),
+                        None => egui::ProgressBar::new(0.0).animate(true).text(format!(
+                            "{}\u{2026} {} MB",
+                            task,
+                            progress.get_done() / 1_000_000
+                        )),
+                    };
+                    ui.put(games_rect, bar);
+                } else if let Some(heatmap) = self.heatmap.as_ref().filter(|_| self.show_heatmap) {
+                    let games = format!("{} games", heatmap.get_games());
+                    ui.put(games_rect, egui::Label::new(games));
+                }
+
+                let explorer_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 410.0,
+                    },
+                    max: Pos2 {
+                        x: 160.0,
+                        y: board_bottom + 440.0,
+                    },
+                };
+                ui.put(
+                    explorer_rect,
+                    egui::Checkbox::new(&mut self.show_explorer, "Opening explorer"),
+                );
+            }
+
+            if let Some(ref piece_info) = self.piece_info {
+                let info_rect = Rect {
+                    min: Pos2 {
+                        x: 0.0,
+                        y: board_bottom + 50.0,
+                    },
+                    max: Pos2 {
+                        x: 8.0 * self.square_size,
+                        y: board_bottom + 80.0,
+                    },
+                };
+                ui.put(info_rect, egui::Label::new(piece_info.as_str()));
+            }
         });
     }
 }
+
+// `amount` of the way from one color to the other
+fn blend(from: Color32, to: Color32, amount: f32) -> Color32 {
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount) as u8;
+    Color32::from_rgb(
+        mix(from.r(), to.r()),
+        mix(from.g(), to.g()),
+        mix(from.b(), to.b()),
+    )
+}
+
+// The games of a PGN file that could be replayed
+fn read_database(path: &Path, progress: &ProgressHandle) -> Result<Vec<ImportedGame>, String> {
+    // progress is counted in bytes of text, which only plain files give the
+    // total of
+    let plain = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+    if let Some(metadata) = fs::metadata(path).ok().filter(|_| plain) {
+        progress.set_total(metadata.len());
+    }
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut games = Vec::new();
+    pgn::open_pgn_file(path)
+        .map_err(ChessError::from)
+        .and_then(|reader| {
+            pgn::import_parallel(
+                reader,
+                PgnMode::Lenient,
+                threads,
+                |imported| games.push(imported),
+                progress,
+            )
+        })
+        .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+    Ok(games)
+}
+
+// $XDG_DATA_HOME, ~/.local/share or %APPDATA%
+fn autosave_path() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(data_dir.join("chess-game").join("autosave.pgn"))
+}
+
+fn game_from_link(link: &str) -> Result<Game, ChessError> {
+    Ok(match share::parse_link(link)? {
+        SharedLink::Position(board, turn) => Game::from_position(board, turn),
+        SharedLink::Game(moves) => {
+            let mut game = Game::new();
+            for mv in moves {
+                if game.make_move(&mv).is_err() {
+                    break;
+                }
+            }
+            game
+        }
+    })
+}
@@ -0,0 +1,188 @@
+use super::{Board, ChessPiece, ChessPieceType, Color};
+use std::fmt;
+
+// Pieces of one side, kings aside
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MaterialCount {
+    pub pawns: u32,
+    pub knights: u32,
+    pub bishops: u32,
+    pub rooks: u32,
+    pub queens: u32,
+}
+
+impl MaterialCount {
+    // The usual 1, 3, 3, 5, 9 pawn values
+    pub fn get_value(&self) -> u32 {
+        self.pawns + 3 * (self.knights + self.bishops) + 5 * self.rooks + 9 * self.queens
+    }
+
+    // Only pawns, rooks and queens can ever force mate on their own
+    pub fn has_major_piece_or_pawn(&self) -> bool {
+        self.pawns + self.rooks + self.queens > 0
+    }
+
+    // Pieces this side has beyond the other side's, kind by kind
+    pub fn excess_over(&self, other: &MaterialCount) -> MaterialCount {
+        MaterialCount {
+            pawns: self.pawns.saturating_sub(other.pawns),
+            knights: self.knights.saturating_sub(other.knights),
+            bishops: self.bishops.saturating_sub(other.bishops),
+            rooks: self.rooks.saturating_sub(other.rooks),
+            queens: self.queens.saturating_sub(other.queens),
+        }
+    }
+
+    fn add(&mut self, piece: &ChessPieceType) {
+        match piece {
+            ChessPieceType::Pawn(_) => self.pawns += 1,
+            ChessPieceType::Knight(_) => self.knights += 1,
+            ChessPieceType::Bishop(_) => self.bishops += 1,
+            ChessPieceType::Rook(_) => self.rooks += 1,
+            ChessPieceType::Queen(_) => self.queens += 1,
+            ChessPieceType::King(_) => {}
+        }
+    }
+
+    fn remove(&mut self, piece: &ChessPieceType) {
+        let count = match piece {
+            ChessPieceType::Pawn(_) => &mut self.pawns,
+            ChessPieceType::Knight(_) => &mut self.knights,
+            ChessPieceType::Bishop(_) => &mut self.bishops,
+            ChessPieceType::Rook(_) => &mut self.rooks,
+            ChessPieceType::Queen(_) => &mut self.queens,
+            ChessPieceType::King(_) => return,
+        };
+        *count = count.saturating_sub(1);
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// e.g. "Q+2P", "-" when there are no pieces
+impl fmt::Display for MaterialCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = [
+            (self.queens, 'Q'),
+            (self.rooks, 'R'),
+            (self.bishops, 'B'),
+            (self.knights, 'N'),
+            (self.pawns, 'P'),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, letter)| match count {
+            1 => letter.to_string(),
+            _ => format!("{}{}", count, letter),
+        })
+        .collect::<Vec<String>>();
+        if parts.is_empty() {
+            write!(f, "-")
+        } else {
+            write!(f, "{}", parts.join("+"))
+        }
+    }
+}
+
+impl Board {
+    pub fn material(&self, color: Color) -> MaterialCount {
+        self.material[color_index(color)]
+    }
+
+    // Writes a square and updates the counts for the pieces it replaces
+    pub(crate) fn set_square(&mut self, row: usize, column: usize, piece: Option<ChessPiece>) {
+        if let Some(old) = self.squares[row][column] {
+            self.material[color_index(old.color)].remove(&old.chess_piece);
+        }
+        if let Some(new) = piece {
+            self.material[color_index(new.color)].add(&new.chess_piece);
+        }
+        self.squares[row][column] = piece;
+    }
+
+    pub(crate) fn take_square(&mut self, row: usize, column: usize) -> Option<ChessPiece> {
+        let piece = self.squares[row][column];
+        self.set_square(row, column, None);
+        piece
+    }
+
+    // Counts from scratch, for boards filled in without set_square
+    pub(crate) fn recount_material(&mut self) {
+        self.material = Default::default();
+        for piece in self.squares.iter().flatten().flatten() {
+            self.material[color_index(piece.color)].add(&piece.chess_piece);
+        }
+    }
+
+    // What each side has that the other lacks, white first, e.g. "R+P vs B+N"
+    pub fn material_imbalance(&self) -> String {
+        let white = self.material(Color::White);
+        let black = self.material(Color::Black);
+        if white == black {
+            return "equal".to_string();
+        }
+        format!(
+            "{} vs {}",
+            white.excess_over(&black),
+            black.excess_over(&white)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Color, Game, MaterialCount};
+
+    fn recounted(board: &Board) -> [MaterialCount; 2] {
+        let mut board = board.clone();
+        board.recount_material();
+        [board.material(Color::White), board.material(Color::Black)]
+    }
+
+    fn counts(board: &Board) -> [MaterialCount; 2] {
+        [board.material(Color::White), board.material(Color::Black)]
+    }
+
+    #[test]
+    fn start_position() {
+        let white = Board::new_game().material(Color::White);
+        assert_eq!(white.to_string(), "Q+2R+2B+2N+8P");
+        assert_eq!(white.get_value(), 39);
+    }
+
+    #[test]
+    fn counts_follow_captures_promotions_and_unmake() {
+        // a capture, en passant, castling and a promotion with capture
+        let moves = [
+            "e2e4", "d7d5", "e4d5", "c7c5", "d5c6", "g8f6", "c6b7", "e7e6", "g1f3", "f8e7", "f1e2",
+            "e8g8", "b7a8q",
+        ];
+        let mut game = Game::new();
+        for uci in moves {
+            game.push_uci(uci).unwrap();
+            assert_eq!(
+                counts(game.get_board()),
+                recounted(game.get_board()),
+                "{}",
+                uci
+            );
+        }
+        assert_eq!(game.get_board().material_imbalance(), "Q+R+2P vs -");
+        while game.unmake_move().is_some() {
+            assert_eq!(counts(game.get_board()), recounted(game.get_board()));
+        }
+        assert_eq!(game.get_board().material_imbalance(), "equal");
+    }
+
+    #[test]
+    fn removing_from_an_empty_count_stays_at_zero() {
+        let mut count = MaterialCount::default();
+        count.remove(&crate::ChessPieceType::Queen(crate::Queen));
+        assert_eq!(count, MaterialCount::default());
+    }
+}
@@ -0,0 +1,510 @@
+use super::{
+    Board, CastlingSide, ChessError, ChessPieceType, Color, Move, Position, PromotionPiece, Result,
+};
+
+fn piece_letter(chess_piece: &ChessPieceType) -> Option<char> {
+    match chess_piece {
+        ChessPieceType::Pawn(_) => None,
+        ChessPieceType::Knight(_) => Some('N'),
+        ChessPieceType::Bishop(_) => Some('B'),
+        ChessPieceType::Rook(_) => Some('R'),
+        ChessPieceType::Queen(_) => Some('Q'),
+        ChessPieceType::King(_) => Some('K'),
+    }
+}
+
+fn promotion_letter(promotion: PromotionPiece) -> char {
+    match promotion {
+        PromotionPiece::Queen => 'Q',
+        PromotionPiece::Rook => 'R',
+        PromotionPiece::Bishop => 'B',
+        PromotionPiece::Knight => 'N',
+    }
+}
+
+fn promotion_from_letter(letter: char) -> Option<PromotionPiece> {
+    PromotionPiece::ALL
+        .into_iter()
+        .find(|&p| promotion_letter(p) == letter.to_ascii_uppercase())
+}
+
+// Splits "e8=Q" or "e8Q" into the move and the promotion piece
+fn strip_promotion(notation: &str) -> Result<(&str, Option<PromotionPiece>)> {
+    match notation.char_indices().last() {
+        Some((i, c @ ('Q' | 'R' | 'B' | 'N'))) if i >= 2 => Ok((
+            notation[..i].trim_end_matches('='),
+            promotion_from_letter(c),
+        )),
+        _ if notation.ends_with('=') => Err(ChessError::ParseError(format!(
+            "missing promotion piece in {:?}",
+            notation
+        ))),
+        _ => Ok((notation, None)),
+    }
+}
+
+fn piece_name(chess_piece: &ChessPieceType) -> &'static str {
+    match chess_piece {
+        ChessPieceType::Pawn(_) => "pawn",
+        ChessPieceType::Knight(_) => "knight",
+        ChessPieceType::Bishop(_) => "bishop",
+        ChessPieceType::Rook(_) => "rook",
+        ChessPieceType::Queen(_) => "queen",
+        ChessPieceType::King(_) => "king",
+    }
+}
+
+// Returns the generated move, which carries the move details
+pub(crate) fn find_legal_move(board: &Board, color: Color, mv: &Move) -> Result<Move> {
+    board
+        .get_all_available_moves(color)
+        .into_iter()
+        .find(|legal| legal == mv)
+        .ok_or_else(|| {
+            board
+                .validate_move(mv.get_from(), mv.get_to())
+                .err()
+                .unwrap_or(ChessError::IllegalDestination)
+        })
+}
+
+fn castling_notation(side: CastlingSide) -> &'static str {
+    match side {
+        CastlingSide::KingSide => "O-O",
+        CastlingSide::QueenSide => "O-O-O",
+    }
+}
+
+// Both the letter O and the digit zero are seen in the wild
+fn find_castling_move(board: &Board, color: Color, notation: &str) -> Option<Result<Move>> {
+    let side = match notation {
+        "O-O" | "0-0" => CastlingSide::KingSide,
+        "O-O-O" | "0-0-0" => CastlingSide::QueenSide,
+        _ => return None,
+    };
+    Some(
+        board
+            .get_all_available_moves(color)
+            .into_iter()
+            .find(|mv| mv.get_castling() == Some(side))
+            .ok_or(ChessError::CastlingNotAllowed),
+    )
+}
+
+fn check_suffix(board: &Board, mv: &Move, color: Color) -> &'static str {
+    let mut shadow_board = board.clone();
+    if shadow_board.make_move(mv).is_err() {
+        return "";
+    }
+    let opponent = color.switch();
+    if !matches!(shadow_board.is_king_in_check(opponent), Ok(Some(_))) {
+        ""
+    } else if shadow_board.get_all_available_moves(opponent).is_empty() {
+        "#"
+    } else {
+        "+"
+    }
+}
+
+pub fn move_to_uci(mv: &Move) -> String {
+    let mut uci = format!("{}{}", mv.get_from(), mv.get_to());
+    if let Some(promotion) = mv.get_promotion() {
+        uci.push(promotion_letter(promotion).to_ascii_lowercase());
+    }
+    uci
+}
+
+pub fn move_from_uci(uci: &str) -> Result<Move> {
+    let invalid = || ChessError::ParseError(format!("invalid UCI move {:?}", uci));
+    if !uci.is_ascii() {
+        return Err(invalid());
+    }
+    let (from, to) = (
+        uci.get(0..2).ok_or_else(invalid)?,
+        uci.get(2..4).ok_or_else(invalid)?,
+    );
+    match uci[4..].chars().collect::<Vec<char>>()[..] {
+        [] => Ok(Move::new(from.parse()?, to.parse()?)),
+        [c] if c.is_ascii_lowercase() => Ok(Move::with_promotion(
+            from.parse()?,
+            to.parse()?,
+            promotion_from_letter(c).ok_or_else(invalid)?,
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+pub fn move_to_san(board: &Board, mv: &Move) -> Result<String> {
+    let piece = board
+        .get_piece(mv.get_from())
+        .as_ref()
+        .ok_or(ChessError::NoPieceAtSquare(mv.get_from()))?;
+    let mv = &find_legal_move(board, piece.color, mv)?;
+
+    if let Some(side) = mv.get_castling() {
+        return Ok(format!(
+            "{}{}",
+            castling_notation(side),
+            check_suffix(board, mv, piece.color)
+        ));
+    }
+
+    let is_capture = mv.is_capture();
+    let mut san = String::new();
+
+    match piece_letter(&piece.chess_piece) {
+        Some(letter) => {
+            san.push(letter);
+
+            // other pieces of the same kind that can reach the same square
+            let rivals = board
+                .get_all_available_moves(piece.color)
+                .into_iter()
+                .filter(|other| other.get_to() == mv.get_to() && other.get_from() != mv.get_from())
+                .filter(|other| {
+                    board
+                        .get_piece(other.get_from())
+                        .as_ref()
+                        .is_some_and(|p| p.chess_piece == piece.chess_piece)
+                })
+                .map(|other| other.get_from())
+                .collect::<Vec<Position>>();
+
+            let from = mv.get_from().to_string();
+            if !rivals.is_empty() {
+                if rivals
+                    .iter()
+                    .all(|pos| pos.get_column() != mv.get_from().get_column())
+                {
+                    san.push_str(&from[0..1]);
+                } else if rivals
+                    .iter()
+                    .all(|pos| pos.get_row() != mv.get_from().get_row())
+                {
+                    san.push_str(&from[1..2]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+        None => {
+            if is_capture {
+                san.push_str(&mv.get_from().to_string()[0..1]);
+            }
+        }
+    }
+
+    if is_capture {
+        san.push('x');
+    }
+    san.push_str(&mv.get_to().to_string());
+    if let Some(promotion) = mv.get_promotion() {
+        san.push('=');
+        san.push(promotion_letter(promotion));
+    }
+
+    san.push_str(check_suffix(board, mv, piece.color));
+
+    Ok(san)
+}
+
+pub fn move_from_san(board: &Board, color: Color, san: &str) -> Result<Move> {
+    let invalid = || ChessError::ParseError(format!("invalid SAN move {:?}", san));
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    if let Some(castling) = find_castling_move(board, color, san) {
+        return castling;
+    }
+    if san.len() < 2 || !san.is_ascii() {
+        return Err(invalid());
+    }
+    let (san, promotion) = strip_promotion(san)?;
+
+    let (letter, rest) = match san.chars().next() {
+        Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (Some(c), &san[1..]),
+        _ => (None, san),
+    };
+    if rest.len() < 2 {
+        return Err(invalid());
+    }
+
+    let to: Position = rest[rest.len() - 2..].parse()?;
+    let qualifier = rest[..rest.len() - 2].trim_end_matches('x');
+
+    let mut from_column = None;
+    let mut from_row = None;
+    for c in qualifier.chars() {
+        match c {
+            'a'..='h' => from_column = Some(c as usize - 'a' as usize),
+            '1'..='8' => from_row = Some(c as usize - '1' as usize),
+            _ => return Err(invalid()),
+        }
+    }
+
+    let mut candidates = board
+        .get_all_available_moves(color)
+        .into_iter()
+        .filter(|mv| mv.get_to() == to && mv.get_promotion() == promotion)
+        .filter(|mv| from_column.is_none_or(|column| mv.get_from().get_column() == column))
+        .filter(|mv| from_row.is_none_or(|row| mv.get_from().get_row() == row))
+        .filter(|mv| {
+            board
+                .get_piece(mv.get_from())
+                .as_ref()
+                .is_some_and(|p| piece_letter(&p.chess_piece) == letter)
+        });
+
+    match (candidates.next(), candidates.next()) {
+        (Some(mv), None) => Ok(mv),
+        (Some(_), Some(_)) => Err(ChessError::AmbiguousMove(san.to_string())),
+        (None, _) => Err(ChessError::IllegalDestination),
+    }
+}
+
+pub fn uci_to_san(board: &Board, uci: &str) -> Result<String> {
+    move_to_san(board, &move_from_uci(uci)?)
+}
+
+pub fn san_to_uci(board: &Board, color: Color, san: &str) -> Result<String> {
+    Ok(move_to_uci(&move_from_san(board, color, san)?))
+}
+
+// Long algebraic notation, e.g. "e2-e4", "Ng1-f3", "Bb5xc6+"
+pub fn move_to_lan(board: &Board, mv: &Move) -> Result<String> {
+    let piece = board
+        .get_piece(mv.get_from())
+        .as_ref()
+        .ok_or(ChessError::NoPieceAtSquare(mv.get_from()))?;
+    let mv = &find_legal_move(board, piece.color, mv)?;
+
+    if let Some(side) = mv.get_castling() {
+        return Ok(format!(
+            "{}{}",
+            castling_notation(side),
+            check_suffix(board, mv, piece.color)
+        ));
+    }
+
+    let mut lan = String::new();
+    if let Some(letter) = piece_letter(&piece.chess_piece) {
+        lan.push(letter);
+    }
+    lan.push_str(&mv.get_from().to_string());
+    lan.push(if mv.is_capture() { 'x' } else { '-' });
+    lan.push_str(&mv.get_to().to_string());
+    if let Some(promotion) = mv.get_promotion() {
+        lan.push('=');
+        lan.push(promotion_letter(promotion));
+    }
+    lan.push_str(check_suffix(board, mv, piece.color));
+
+    Ok(lan)
+}
+
+pub fn move_from_lan(board: &Board, color: Color, lan: &str) -> Result<Move> {
+    let invalid = || ChessError::ParseError(format!("invalid LAN move {:?}", lan));
+    let lan = lan.trim_end_matches(['+', '#', '!', '?']);
+    if let Some(castling) = find_castling_move(board, color, lan) {
+        return castling;
+    }
+    if !lan.is_ascii() {
+        return Err(invalid());
+    }
+    let (lan, promotion) = strip_promotion(lan)?;
+
+    let (letter, rest) = match lan.chars().next() {
+        Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (Some(c), &lan[1..]),
+        _ => (None, lan),
+    };
+    if rest.len() != 5 || !matches!(&rest[2..3], "-" | "x") {
+        return Err(invalid());
+    }
+
+    let from: Position = rest[0..2].parse()?;
+    let to: Position = rest[3..5].parse()?;
+    let piece = board
+        .get_piece(from)
+        .as_ref()
+        .ok_or(ChessError::NoPieceAtSquare(from))?;
+    if piece_letter(&piece.chess_piece) != letter {
+        return Err(invalid());
+    }
+
+    let mv = match promotion {
+        Some(promotion) => Move::with_promotion(from, to, promotion),
+        None => Move::new(from, to),
+    };
+    find_legal_move(board, color, &mv)
+}
+
+// Plain words for screen readers, e.g. "White knight g1 to f3, check"
+pub fn move_to_speech(board: &Board, mv: &Move) -> Result<String> {
+    let piece = board
+        .get_piece(mv.get_from())
+        .as_ref()
+        .ok_or(ChessError::NoPieceAtSquare(mv.get_from()))?;
+    let mv = &find_legal_move(board, piece.color, mv)?;
+
+    let mut speech = format!("{:?} ", piece.color);
+    match mv.get_castling() {
+        Some(CastlingSide::KingSide) => speech.push_str("castles kingside"),
+        Some(CastlingSide::QueenSide) => speech.push_str("castles queenside"),
+        None => {
+            speech.push_str(&format!(
+                "{} {} to {}",
+                piece_name(&piece.chess_piece),
+                mv.get_from(),
+                mv.get_to()
+            ));
+            if let Some(captured) = mv.get_captured() {
+                speech.push_str(&format!(" takes {}", piece_name(&captured.chess_piece)));
+            }
+            if mv.is_en_passant() {
+                speech.push_str(" en passant");
+            }
+            if let Some(promotion) = mv.get_promotion() {
+                speech.push_str(&format!(
+                    ", promotes to {}",
+                    piece_name(&promotion.to_chess_piece_type())
+                ));
+            }
+        }
+    }
+    speech.push_str(match check_suffix(board, mv, piece.color) {
+        "+" => ", check",
+        "#" => ", checkmate",
+        _ => "",
+    });
+    Ok(speech)
+}
+
+// ICCF numeric notation, e.g. "5254" for e2-e4, with a fifth digit for
+// promotions: 1 queen, 2 rook, 3 bishop, 4 knight
+pub fn move_to_iccf(mv: &Move) -> String {
+    let mut iccf = format!(
+        "{}{}{}{}",
+        mv.get_from().get_column() + 1,
+        mv.get_from().get_row() + 1,
+        mv.get_to().get_column() + 1,
+        mv.get_to().get_row() + 1
+    );
+    if let Some(promotion) = mv.get_promotion() {
+        let index = PromotionPiece::ALL
+            .iter()
+            .position(|&p| p == promotion)
+            .unwrap();
+        iccf.push_str(&(index + 1).to_string());
+    }
+    iccf
+}
+
+pub fn move_from_iccf(iccf: &str) -> Result<Move> {
+    let invalid = || ChessError::ParseError(format!("invalid ICCF move {:?}", iccf));
+    let digits = iccf
+        .chars()
+        .map(|c| match c {
+            '1'..='8' => Ok(c as usize - '1' as usize),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
+    match digits[..] {
+        [from_column, from_row, to_column, to_row] => Ok(Move::new(
+            Position::try_new(from_row, from_column)?,
+            Position::try_new(to_row, to_column)?,
+        )),
+        [from_column, from_row, to_column, to_row, promotion] => Ok(Move::with_promotion(
+            Position::try_new(from_row, from_column)?,
+            Position::try_new(to_row, to_column)?,
+            *PromotionPiece::ALL.get(promotion).ok_or_else(invalid)?,
+        )),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn board(fen: &str) -> Board {
+        Game::from_fen(fen).unwrap().get_board().clone()
+    }
+
+    fn san_of(board: &Board, uci: &str) -> String {
+        move_to_san(board, &uci.parse().unwrap()).unwrap()
+    }
+
+    fn uci_of(mv: Result<Move>) -> String {
+        mv.unwrap().to_uci()
+    }
+
+    #[test]
+    fn san_names_the_file_or_rank_when_needed() {
+        let rooks = board("4k3/8/8/R7/8/8/8/RN2KN2 w - - 0 1");
+        for (uci, san) in [
+            ("a1a3", "R1a3"),
+            ("a5a3", "R5a3"),
+            ("b1d2", "Nbd2"),
+            ("f1d2", "Nfd2"),
+            ("b1c3", "Nc3"),
+        ] {
+            assert_eq!(san_of(&rooks, uci), san);
+            assert_eq!(uci_of(move_from_san(&rooks, Color::White, san)), uci);
+        }
+        assert!(matches!(
+            move_from_san(&rooks, Color::White, "Ra3"),
+            Err(ChessError::AmbiguousMove(_))
+        ));
+
+        // each queen shares a file or a rank with another one
+        let queens = board("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1");
+        for (uci, san) in [("a1c3", "Qa1c3"), ("a3c3", "Q3c3"), ("c1c3", "Qcc3")] {
+            assert_eq!(san_of(&queens, uci), san);
+            assert_eq!(uci_of(move_from_san(&queens, Color::White, san)), uci);
+        }
+    }
+
+    #[test]
+    fn long_algebraic_round_trip() {
+        let mut game = Game::new();
+        for uci in ["e2e4", "e7e5", "f1c4", "b8c6"] {
+            game.push_uci(uci).unwrap();
+        }
+        let promotion = board("8/4P3/8/8/k7/8/8/4K3 w - - 0 1");
+        let castling = board("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        for (board, uci, lan) in [
+            (game.get_board(), "g1f3", "Ng1-f3"),
+            (game.get_board(), "c4f7", "Bc4xf7+"),
+            (game.get_board(), "d2d4", "d2-d4"),
+            (&promotion, "e7e8q", "e7-e8=Q+"),
+            (&castling, "e1g1", "O-O"),
+        ] {
+            let mv = uci.parse().unwrap();
+            assert_eq!(move_to_lan(board, &mv).unwrap(), lan);
+            assert_eq!(uci_of(move_from_lan(board, Color::White, lan)), uci);
+        }
+
+        let start = Board::new_game();
+        // the wrong piece, a missing separator and a move that isn't legal
+        for lan in ["Bb1-c3", "e2e4", "e2-e5"] {
+            assert!(move_from_lan(&start, Color::White, lan).is_err(), "{}", lan);
+        }
+    }
+
+    #[test]
+    fn iccf_round_trip() {
+        for (uci, iccf) in [
+            ("e2e4", "5254"),
+            ("g8f6", "7866"),
+            ("e7e8q", "57581"),
+            ("a2a1n", "12114"),
+        ] {
+            let mv: Move = uci.parse().unwrap();
+            assert_eq!(move_to_iccf(&mv), iccf);
+            assert_eq!(uci_of(move_from_iccf(iccf)), uci);
+        }
+        for iccf in ["525", "5259", "52540", "57585", "e2e4"] {
+            assert!(move_from_iccf(iccf).is_err(), "{}", iccf);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use super::pgn::ImportedGame;
+use super::{fen, Board, Color, Move};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OpeningStats {
+    pub games: usize,
+    pub white_wins: usize,
+    pub draws: usize,
+    pub black_wins: usize,
+    rating_sum: u64,
+    rated_games: usize,
+}
+
+impl OpeningStats {
+    fn add(&mut self, result: &str, rating: Option<u32>) {
+        self.games += 1;
+        match result {
+            "1-0" => self.white_wins += 1,
+            "0-1" => self.black_wins += 1,
+            "1/2-1/2" => self.draws += 1,
+            _ => {}
+        }
+        if let Some(rating) = rating {
+            self.rating_sum += rating as u64;
+            self.rated_games += 1;
+        }
+    }
+
+    // Score of the white side, between 0 and 1, counting only decisive and drawn games
+    pub fn get_white_score(&self) -> Option<f64> {
+        let finished = self.white_wins + self.draws + self.black_wins;
+        if finished == 0 {
+            return None;
+        }
+        Some((self.white_wins as f64 + self.draws as f64 / 2.0) / finished as f64)
+    }
+
+    // Average rating of the players who chose the move
+    pub fn get_average_rating(&self) -> Option<u32> {
+        if self.rated_games == 0 {
+            return None;
+        }
+        Some((self.rating_sum / self.rated_games as u64) as u32)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct OpeningNode {
+    stats: OpeningStats,
+    children: Vec<(Move, OpeningNode)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpeningTree {
+    root: OpeningNode,
+    max_plies: usize,
+}
+
+impl OpeningTree {
+    pub fn new(max_plies: usize) -> Self {
+        OpeningTree {
+            root: OpeningNode::default(),
+            max_plies,
+        }
+    }
+
+    // The tree starts from the initial position, games set up from another
+    // position are left out
+    pub fn add_game(&mut self, imported: &ImportedGame) {
+        if let Some(fen) = imported.game.get_tag("FEN") {
+            let initial = fen::parse_fen(fen).is_ok_and(|position| {
+                position.turn == Color::White && position.board == Board::new_game()
+            });
+            if !initial {
+                return;
+            }
+        }
+        let result = imported.game.result.as_str();
+        let rating = |tag| {
+            imported
+                .game
+                .get_tag(tag)
+                .and_then(|rating| rating.parse::<u32>().ok())
+        };
+        let (white_rating, black_rating) = (rating("WhiteElo"), rating("BlackElo"));
+        // the rating of the player who made the move
+        let rating_of = |mv: &Move| match mv.get_piece()?.color {
+            Color::White => white_rating,
+            Color::Black => black_rating,
+        };
+
+        let mut node = &mut self.root;
+        node.stats.add(result, None);
+        for mv in imported.moves.iter().take(self.max_plies) {
+            let index = match node.children.iter().position(|(child, _)| child == mv) {
+                Some(index) => index,
+                None => {
+                    node.children.push((*mv, OpeningNode::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index].1;
+            node.stats.add(result, rating_of(mv));
+        }
+    }
+
+    pub fn get_stats(&self, line: &[Move]) -> Option<OpeningStats> {
+        self.find_node(line).map(|node| node.stats)
+    }
+
+    // Continuations played after `line`, most popular first
+    pub fn get_moves(&self, line: &[Move]) -> Vec<(Move, OpeningStats)> {
+        let mut moves = self
+            .find_node(line)
+            .map(|node| {
+                node.children
+                    .iter()
+                    .map(|(mv, child)| (*mv, child.stats))
+                    .collect::<Vec<(Move, OpeningStats)>>()
+            })
+            .unwrap_or_default();
+        moves.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.games));
+        moves
+    }
+
+    fn find_node(&self, line: &[Move]) -> Option<&OpeningNode> {
+        let mut node = &self.root;
+        for mv in line {
+            node = &node.children.iter().find(|(child, _)| child == mv)?.1;
+        }
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::{PgnMode, PgnReader};
+    use crate::Game;
+
+    fn imported(pgn: &str) -> ImportedGame {
+        let game = PgnReader::new(pgn.as_bytes(), PgnMode::Lenient)
+            .next()
+            .unwrap()
+            .unwrap();
+        let moves = game.validate().unwrap();
+        ImportedGame { game, moves }
+    }
+
+    fn line(moves: &[&str]) -> Vec<Move> {
+        let mut game = Game::new();
+        for uci in moves {
+            game.push_uci(uci).unwrap();
+        }
+        game.get_moves().to_vec()
+    }
+
+    fn tree(games: &[&str]) -> OpeningTree {
+        let mut tree = OpeningTree::new(3);
+        for pgn in games {
+            tree.add_game(&imported(pgn));
+        }
+        tree
+    }
+
+    #[test]
+    fn counts_results_and_ratings() {
+        let tree = tree(&[
+            "[WhiteElo \"2000\"]\n[BlackElo \"1800\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0",
+            "[WhiteElo \"2200\"]\n[BlackElo \"1600\"]\n\n1. e4 c5 1/2-1/2",
+            "[WhiteElo \"1500\"]\n\n1. e4 c5 0-1",
+            "1. d4 d5 *",
+        ]);
+        let root = tree.get_stats(&[]).unwrap();
+        assert_eq!(
+            (root.games, root.white_wins, root.draws, root.black_wins),
+            (4, 1, 1, 1)
+        );
+        assert_eq!(root.get_white_score(), Some(0.5));
+
+        let e4 = tree.get_stats(&line(&["e2e4"])).unwrap();
+        assert_eq!(e4.games, 3);
+        assert_eq!(e4.get_average_rating(), Some(1900));
+        // black's moves are rated by black's rating
+        let c5 = tree.get_stats(&line(&["e2e4", "c7c5"])).unwrap();
+        assert_eq!(c5.get_average_rating(), Some(1600));
+        assert_eq!(c5.get_white_score(), Some(0.25));
+
+        let replies = tree.get_moves(&line(&["e2e4"]));
+        let replies = replies.iter().map(|(mv, stats)| (mv.to_uci(), stats.games));
+        assert_eq!(
+            replies.collect::<Vec<(String, usize)>>(),
+            [("c7c5".to_string(), 2), ("e7e5".to_string(), 1)]
+        );
+        // only the first three plies are kept
+        assert!(tree.get_moves(&line(&["e2e4", "e7e5", "g1f3"])).is_empty());
+        assert_eq!(tree.get_stats(&line(&["c2c4"])), None);
+    }
+
+    #[test]
+    fn games_from_other_positions_are_left_out() {
+        let tree = tree(&[
+            "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 *",
+            "[SetUp \"1\"]\n[FEN \"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\"]\n\n1. e4 *",
+        ]);
+        assert_eq!(tree.get_stats(&[]).unwrap().games, 1);
+        assert_eq!(tree.get_stats(&line(&["e2e4"])).unwrap().games, 1);
+    }
+}
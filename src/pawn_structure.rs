@@ -0,0 +1,133 @@
+use super::{Board, ChessPieceType, Color, Pawn, Position};
+
+impl Board {
+    fn is_pawn_of(&self, pos: Position, color: Color) -> bool {
+        self.get_piece(pos)
+            .as_ref()
+            .is_some_and(|p| p.color == color && matches!(p.chess_piece, ChessPieceType::Pawn(_)))
+    }
+
+    // No enemy pawn ahead of it on its own or a neighbouring column
+    pub fn is_passed_pawn(&self, pos: Position) -> bool {
+        let color = match self.get_piece(pos) {
+            Some(piece) if matches!(piece.chess_piece, ChessPieceType::Pawn(_)) => piece.color,
+            _ => return false,
+        };
+        let ahead = |row: usize| match color {
+            Color::White => row > pos.get_row(),
+            Color::Black => row < pos.get_row(),
+        };
+        let column = pos.get_column();
+        (column.saturating_sub(1)..=(column + 1).min(7)).all(|column| {
+            (0..8).filter(|&row| ahead(row)).all(|row| {
+                !self.is_pawn_of(Position::try_new(row, column).unwrap(), color.switch())
+            })
+        })
+    }
+
+    pub fn get_passed_pawns(&self, color: Color) -> Vec<Position> {
+        let mut pawns = Vec::new();
+        for row in 0..8 {
+            for column in 0..8 {
+                let pos = Position::try_new(row, column).unwrap();
+                if self.is_pawn_of(pos, color) && self.is_passed_pawn(pos) {
+                    pawns.push(pos);
+                }
+            }
+        }
+        pawns
+    }
+
+    // The squares in front of a pawn up to its promotion square
+    pub fn get_promotion_path(&self, pawn: Position) -> Vec<Position> {
+        let color = match self.get_piece(pawn) {
+            Some(piece) if matches!(piece.chess_piece, ChessPieceType::Pawn(_)) => piece.color,
+            _ => return Vec::new(),
+        };
+        (1..8)
+            .map_while(|distance| Pawn.move_up(pawn, distance, color))
+            .collect()
+    }
+
+    // Moves a pawn needs to promote when nothing stops it, the first move
+    // may be a double step
+    pub fn get_promotion_distance(&self, pawn: Position) -> Option<usize> {
+        let color = match self.get_piece(pawn) {
+            Some(piece) if matches!(piece.chess_piece, ChessPieceType::Pawn(_)) => piece.color,
+            _ => return None,
+        };
+        let distance = Pawn.get_promotion_row(color).abs_diff(pawn.get_row());
+        if pawn.get_row() == Pawn.get_starting_row(color) {
+            Some(distance - 1)
+        } else {
+            Some(distance)
+        }
+    }
+
+    // The rule of the square: whether the defending king catches the pawn
+    // in a race, ignoring every other piece
+    pub fn is_king_in_square(&self, pawn: Position, turn: Color) -> bool {
+        let (Some(distance), Some(piece)) =
+            (self.get_promotion_distance(pawn), self.get_piece(pawn))
+        else {
+            return false;
+        };
+        let defender = piece.color.switch();
+        let Ok(king) = self.get_king(defender) else {
+            return false;
+        };
+        let promotion_row = Pawn.get_promotion_row(piece.color);
+        let king_distance = king
+            .get_column()
+            .abs_diff(pawn.get_column())
+            .max(king.get_row().abs_diff(promotion_row));
+        king_distance <= distance + usize::from(turn == defender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn board(fen: &str) -> Board {
+        Game::from_fen(fen).unwrap().get_board().clone()
+    }
+
+    fn pos(square: &str) -> Position {
+        square.parse().unwrap()
+    }
+
+    #[test]
+    fn pawns_are_passed_without_enemy_pawns_ahead() {
+        let board = board("4k3/p7/8/2P5/8/3p4/P7/4K3 w - - 0 1");
+        assert_eq!(board.get_passed_pawns(Color::White), [pos("c5")]);
+        assert_eq!(board.get_passed_pawns(Color::Black), [pos("d3")]);
+        assert!(!board.is_passed_pawn(pos("e1")));
+
+        assert_eq!(
+            board.get_promotion_path(pos("c5")),
+            [pos("c6"), pos("c7"), pos("c8")]
+        );
+        assert_eq!(board.get_promotion_path(pos("d3")), [pos("d2"), pos("d1")]);
+        assert!(board.get_promotion_path(pos("e8")).is_empty());
+
+        // the pawn on a2 can still step twice
+        assert_eq!(board.get_promotion_distance(pos("a2")), Some(5));
+        assert_eq!(board.get_promotion_distance(pos("c5")), Some(3));
+        assert_eq!(board.get_promotion_distance(pos("d3")), Some(2));
+        assert_eq!(board.get_promotion_distance(pos("e1")), None);
+    }
+
+    #[test]
+    fn the_king_catches_pawns_inside_its_square() {
+        let near = board("6k1/8/8/2P5/8/8/8/4K3 w - - 0 1");
+        // one step too far unless the king moves first
+        assert!(!near.is_king_in_square(pos("c5"), Color::White));
+        assert!(near.is_king_in_square(pos("c5"), Color::Black));
+
+        let far = board("7k/8/8/2P5/8/8/8/4K3 w - - 0 1");
+        assert!(!far.is_king_in_square(pos("c5"), Color::Black));
+        assert!(!far.is_king_in_square(pos("h8"), Color::Black));
+    }
+}
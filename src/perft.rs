@@ -0,0 +1,98 @@
+use super::{Board, Color, Move};
+
+impl Board {
+    // Number of leaf positions after `depth` plies of legal moves, the standard
+    // check of a move generator against published counts
+    pub fn perft(&self, color: Color, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.get_all_available_moves(color);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mv| {
+                let mut board = self.clone();
+                board.apply_move(mv);
+                board.perft(color.switch(), depth - 1)
+            })
+            .sum()
+    }
+
+    // perft split by root move, to find which move a wrong count comes from
+    pub fn perft_divide(&self, color: Color, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        self.get_all_available_moves(color)
+            .into_iter()
+            .map(|mv| {
+                let mut board = self.clone();
+                board.apply_move(&mv);
+                (mv, board.perft(color.switch(), depth - 1))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fen, Board, Color};
+
+    // Published counts from the Chess Programming Wiki
+    fn perft(fen: &str, depth: u32) -> u64 {
+        let position = fen::parse_fen(fen).unwrap();
+        position.board.perft(position.turn, depth)
+    }
+
+    #[test]
+    fn start_position() {
+        let board = Board::new_game();
+        assert_eq!(board.perft(Color::White, 1), 20);
+        assert_eq!(board.perft(Color::White, 2), 400);
+        assert_eq!(board.perft(Color::White, 3), 8902);
+    }
+
+    #[test]
+    fn kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(perft(fen, 1), 48);
+        assert_eq!(perft(fen, 2), 2039);
+        assert_eq!(perft(fen, 3), 97862);
+    }
+
+    #[test]
+    fn en_passant_and_discovered_checks() {
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        assert_eq!(perft(fen, 1), 14);
+        assert_eq!(perft(fen, 2), 191);
+        assert_eq!(perft(fen, 3), 2812);
+        assert_eq!(perft(fen, 4), 43238);
+    }
+
+    #[test]
+    fn promotions_and_castling_out_of_check() {
+        let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+        assert_eq!(perft(fen, 1), 6);
+        assert_eq!(perft(fen, 2), 264);
+        assert_eq!(perft(fen, 3), 9467);
+    }
+
+    #[test]
+    fn promotion_with_check() {
+        let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+        assert_eq!(perft(fen, 1), 44);
+        assert_eq!(perft(fen, 2), 1486);
+        assert_eq!(perft(fen, 3), 62379);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = Board::new_game();
+        let divided = board.perft_divide(Color::White, 3);
+        assert_eq!(divided.len(), 20);
+        assert_eq!(divided.iter().map(|(_, count)| count).sum::<u64>(), 8902);
+    }
+}
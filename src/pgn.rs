@@ -0,0 +1,1273 @@
+use super::{
+    notation, progress::ProgressHandle, Board, ChessError, Color, Game, GameStatus, GameTree, Move,
+    Result,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    iter::Peekable,
+    path::Path,
+    str::Chars,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+// Variant tag values that still mean standard chess, "From Position" is
+// what some sites write for set-up games
+const STANDARD_VARIANTS: [&str; 3] = ["Standard", "Normal", "From Position"];
+
+// Code points for the 0x80..=0x9F range of Windows-1252, the rest maps to Latin-1
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PgnMode {
+    #[default]
+    Strict,
+    // Recover from common real-world defects, reporting them as warnings
+    Lenient,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub variations: Vec<PgnVariation>,
+    pub result: String,
+}
+
+// A line played instead of the move at `ply` in the enclosing line
+#[derive(Debug, Default, Clone)]
+pub struct PgnVariation {
+    pub ply: usize,
+    pub moves: Vec<String>,
+    pub variations: Vec<PgnVariation>,
+}
+
+// The result token for a game's current status, "*" while it's still going
+fn result_token(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::Ongoing | GameStatus::Check => "*",
+        GameStatus::Checkmate {
+            winner: Color::White,
+        } => "1-0",
+        GameStatus::Checkmate {
+            winner: Color::Black,
+        } => "0-1",
+        GameStatus::Stalemate | GameStatus::Draw { .. } => "1/2-1/2",
+    }
+}
+
+impl PgnGame {
+    // Tags the game doesn't know about are left for to_pgn to fill in
+    pub fn from_game(game: &Game) -> Self {
+        let result = result_token(game.status()).to_string();
+        let mut tags = vec![
+            ("Date".to_string(), "????.??.??".to_string()),
+            ("Result".to_string(), result.clone()),
+        ];
+        for (tag, color) in [("White", Color::White), ("Black", Color::Black)] {
+            if let Some(player) = game.get_player(color) {
+                tags.push((tag.to_string(), player.to_string()));
+            }
+        }
+        let (starting_board, starting_turn) = game.get_starting_position();
+        if *starting_board != Board::new_game() || starting_turn != Color::White {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), game.get_starting_fen()));
+        }
+        PgnGame {
+            tags,
+            moves: game.get_san_moves(),
+            variations: Vec::new(),
+            result,
+        }
+    }
+
+    // The main line is exported like a game, the other lines as variations
+    pub fn from_game_tree(tree: &GameTree) -> Self {
+        let mut tree = tree.clone();
+        let mut pgn_game = PgnGame::from_game(&tree.get_main_line_game());
+        pgn_game.variations = variations_from_tree(&mut tree, GameTree::ROOT).1;
+        pgn_game
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn to_pgn(&self, options: &PgnExportOptions) -> String {
+        let mut pgn = String::new();
+        let mut write_tag = |name: &str, value: &str| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        };
+
+        for tag in SEVEN_TAG_ROSTER {
+            let value = match tag {
+                "Result" => self.get_tag(tag).unwrap_or(&self.result),
+                _ => self.get_tag(tag).unwrap_or("?"),
+            };
+            write_tag(tag, value);
+        }
+        for tag in &options.optional_tags {
+            if let Some(value) = self.get_tag(tag) {
+                write_tag(tag, value);
+            }
+        }
+        pgn.push('\n');
+
+        // games set up from a FEN may start with black to move or a later move number
+        let fen_fields = self
+            .get_tag("FEN")
+            .map(|fen| fen.split_whitespace().collect::<Vec<&str>>())
+            .unwrap_or_default();
+        let black_first = fen_fields.get(1) == Some(&"b");
+        let first_move = fen_fields
+            .get(5)
+            .and_then(|number| number.parse::<usize>().ok())
+            .unwrap_or(1);
+
+        let mut tokens = Vec::new();
+        push_movetext(
+            &mut tokens,
+            &self.moves,
+            &self.variations,
+            usize::from(black_first),
+            first_move,
+        );
+        tokens.push(if self.result.is_empty() {
+            "*".to_string()
+        } else {
+            self.result.clone()
+        });
+
+        // export format lines are at most 80 characters long
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > 80 {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push_str("\n\n");
+        pgn
+    }
+
+    // Replays the movetext from the starting position, by the same rules as
+    // to_game so a game that validates can be played
+    pub fn validate(&self) -> Result<Vec<Move>> {
+        Ok(self.to_game()?.get_moves().to_vec())
+    }
+
+    // The game before any moves, set up from the FEN tag if there is one.
+    // Only standard chess is played, other variants' moves and castling
+    // rules would be misread.
+    fn starting_game(&self) -> Result<Game> {
+        if let Some(variant) = self.get_tag("Variant") {
+            if !STANDARD_VARIANTS
+                .iter()
+                .any(|standard| standard.eq_ignore_ascii_case(variant))
+            {
+                return Err(ChessError::ParseError(format!(
+                    "unsupported variant: {}",
+                    variant
+                )));
+            }
+        }
+        let mut builder = Game::builder();
+        if let Some(fen) = self.get_tag("FEN") {
+            builder = builder.fen(fen)?;
+        }
+        for (tag, color) in [("White", Color::White), ("Black", Color::Black)] {
+            if let Some(player) = self.get_tag(tag).filter(|&player| player != "?") {
+                builder = builder.player(color, player);
+            }
+        }
+        Ok(builder.build())
+    }
+
+    // Plays the main line into a Game, the variations are left out
+    pub fn to_game(&self) -> Result<Game> {
+        let mut game = self.starting_game()?;
+        for san in &self.moves {
+            game.make_move(&read_move(game.get_board(), game.get_turn(), san)?)?;
+        }
+        Ok(game)
+    }
+
+    // Plays the main line and the variations, ending at the main line's last move
+    pub fn to_game_tree(&self) -> Result<GameTree> {
+        let mut tree = GameTree::new(self.starting_game()?);
+        let last = add_line(&mut tree, GameTree::ROOT, &self.moves, &self.variations)?;
+        tree.go_to(last);
+        Ok(tree)
+    }
+}
+
+// Movetext should be SAN, but some programs write long algebraic or ICCF
+// numeric moves, which are tried in that order. The SAN error is the one
+// reported.
+fn read_move(board: &Board, color: Color, text: &str) -> Result<Move> {
+    notation::move_from_san(board, color, text).or_else(|san_error| {
+        notation::move_from_lan(board, color, text)
+            .or_else(|_| notation::move_from_iccf(text))
+            .map_err(|_| san_error)
+    })
+}
+
+// Appends the move numbers, moves and parenthesized variations of a line
+fn push_movetext(
+    tokens: &mut Vec<String>,
+    moves: &[String],
+    variations: &[PgnVariation],
+    first_ply: usize,
+    first_move: usize,
+) {
+    // black moves get a number at the start of a line and after a variation
+    let mut number_black = true;
+    for (i, san) in moves.iter().enumerate() {
+        let ply = first_ply + i;
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", first_move + ply / 2));
+        } else if number_black {
+            tokens.push(format!("{}...", first_move + ply / 2));
+        }
+        tokens.push(san.clone());
+        number_black = false;
+
+        for variation in variations.iter().filter(|variation| variation.ply == i) {
+            let start = tokens.len();
+            push_movetext(
+                tokens,
+                &variation.moves,
+                &variation.variations,
+                ply,
+                first_move,
+            );
+            if tokens.len() > start {
+                tokens[start].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
+                number_black = true;
+            }
+        }
+    }
+}
+
+// Plays the line from the node and returns the node of its last move
+fn add_line(
+    tree: &mut GameTree,
+    start: usize,
+    moves: &[String],
+    variations: &[PgnVariation],
+) -> Result<usize> {
+    tree.go_to(start);
+    let mut nodes = vec![start];
+    for san in moves {
+        let game = tree.get_game();
+        let mv = read_move(game.get_board(), game.get_turn(), san)?;
+        tree.make_move(&mv)?;
+        nodes.push(tree.get_current());
+    }
+    for variation in variations {
+        let start = *nodes.get(variation.ply).ok_or_else(|| {
+            ChessError::ParseError(format!(
+                "variation at ply {} of a line with {} moves",
+                variation.ply + 1,
+                moves.len()
+            ))
+        })?;
+        add_line(tree, start, &variation.moves, &variation.variations)?;
+    }
+    Ok(nodes[nodes.len() - 1])
+}
+
+// The SAN moves of the line the node continues with, and the lines branching
+// off it
+fn variations_from_tree(tree: &mut GameTree, start: usize) -> (Vec<String>, Vec<PgnVariation>) {
+    let mut moves = Vec::new();
+    let mut variations = Vec::new();
+    let mut node = start;
+    while let Some((&next, alternatives)) = tree.get_children(node).to_vec().split_first() {
+        for &alternative in alternatives {
+            tree.go_to(node);
+            let san = move_san(tree, alternative);
+            let (mut line, mut nested) = variations_from_tree(tree, alternative);
+            line.insert(0, san);
+            for variation in &mut nested {
+                variation.ply += 1;
+            }
+            variations.push(PgnVariation {
+                ply: moves.len(),
+                moves: line,
+                variations: nested,
+            });
+        }
+        tree.go_to(node);
+        moves.push(move_san(tree, next));
+        node = next;
+    }
+    (moves, variations)
+}
+
+// SAN for a child of the current node
+fn move_san(tree: &GameTree, child: usize) -> String {
+    // every move in the tree was legal when it was played
+    let mv = tree.get_move(child).unwrap();
+    notation::move_to_san(tree.get_game().get_board(), mv).unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnExportOptions {
+    // Tags written after the Seven Tag Roster, in this order, when the game has them
+    pub optional_tags: Vec<String>,
+}
+
+impl Default for PgnExportOptions {
+    fn default() -> Self {
+        PgnExportOptions {
+            optional_tags: [
+                "ECO",
+                "TimeControl",
+                "Termination",
+                "SetUp",
+                "FEN",
+                "Variant",
+            ]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PgnImport {
+    pub games: Vec<PgnGame>,
+    pub warnings: Vec<String>,
+}
+
+enum Token {
+    Tag(String, String),
+    Move(String),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+}
+
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    mode: PgnMode,
+    line: usize,
+    pending: Option<Token>,
+    warnings: &'a mut Vec<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(text: &'a str, mode: PgnMode, warnings: &'a mut Vec<String>) -> Self {
+        Tokenizer {
+            chars: text.chars().peekable(),
+            mode,
+            line: 1,
+            pending: None,
+            warnings,
+        }
+    }
+
+    fn defect(&mut self, message: &str) -> Result<()> {
+        match self.mode {
+            PgnMode::Strict => Err(ChessError::ParseError(format!(
+                "line {}: {}",
+                self.line, message
+            ))),
+            PgnMode::Lenient => {
+                self.warnings
+                    .push(format!("line {}: {}", self.line, message));
+                Ok(())
+            }
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) -> Result<()> {
+        while let Some(c) = self.bump() {
+            if c == '}' {
+                return Ok(());
+            }
+        }
+        self.defect("unterminated comment")
+    }
+
+    fn read_tag(&mut self) -> Result<Option<Token>> {
+        let mut name = String::new();
+        let mut value = String::new();
+        let mut in_string = false;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' if in_string => {
+                    if let Some(escaped) = self.bump() {
+                        value.push(escaped);
+                    }
+                }
+                '"' => in_string = !in_string,
+                _ if in_string => value.push(c),
+                ']' => {
+                    let name = name.trim();
+                    if name.is_empty() || name.contains(char::is_whitespace) {
+                        return self.defect("malformed tag").map(|_| None);
+                    }
+                    return Ok(Some(Token::Tag(name.to_string(), value)));
+                }
+                '\n' => break,
+                _ => name.push(c),
+            }
+        }
+        self.defect("unterminated tag").map(|_| None)
+    }
+
+    fn read_word(&mut self) -> Result<Option<Token>> {
+        let mut word = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || "{}();[".contains(c) {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+
+        if RESULTS.contains(&word.as_str()) {
+            return Ok(Some(Token::Result(word)));
+        }
+
+        // move numbers such as "12." or "12..." may be glued to the move
+        let word = match word.find(|c: char| !c.is_ascii_digit()) {
+            Some(i) if i > 0 && word[i..].starts_with('.') => {
+                word[i..].trim_start_matches('.').to_string()
+            }
+            _ => word,
+        };
+
+        // "!?" written apart from its move is an annotation like $5
+        if word.chars().all(|c| c == '!' || c == '?') {
+            return Ok(None);
+        }
+        if let Some(result) = RESULTS
+            .iter()
+            .find(|result| word.len() > result.len() && word.ends_with(*result))
+        {
+            self.defect("result glued to the last move")?;
+            self.pending = Some(Token::Result(result.to_string()));
+            return Ok(Some(Token::Move(
+                word[..word.len() - result.len()].to_string(),
+            )));
+        }
+        Ok(Some(Token::Move(word)))
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        if let Some(token) = self.pending.take() {
+            return Ok(Some(token));
+        }
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '[' => {
+                    self.bump();
+                    if let Some(tag) = self.read_tag()? {
+                        return Ok(Some(tag));
+                    }
+                }
+                '{' => {
+                    self.bump();
+                    self.skip_comment()?;
+                }
+                '(' => {
+                    self.bump();
+                    return Ok(Some(Token::VariationStart));
+                }
+                ')' => {
+                    self.bump();
+                    return Ok(Some(Token::VariationEnd));
+                }
+                ';' | '%' => self.skip_line(),
+                '}' => {
+                    self.bump();
+                    self.defect(&format!("stray '{}'", c))?;
+                }
+                '$' => {
+                    self.bump();
+                    while self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.bump();
+                    }
+                }
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                _ => {
+                    if let Some(token) = self.read_word()? {
+                        return Ok(Some(token));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn decode(input: &[u8], mode: PgnMode, warnings: &mut Vec<String>) -> Result<String> {
+    match std::str::from_utf8(input) {
+        Ok(text) => Ok(text.to_string()),
+        Err(_) if mode == PgnMode::Lenient => {
+            warnings.push("input is not valid UTF-8, decoded as Windows-1252".to_string());
+            Ok(input
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize],
+                    _ => b as char,
+                })
+                .collect())
+        }
+        Err(_) => Err(ChessError::ParseError(
+            "input is not valid UTF-8".to_string(),
+        )),
+    }
+}
+
+fn finish_game(
+    mut game: PgnGame,
+    index: usize,
+    mode: PgnMode,
+    warnings: &mut Vec<String>,
+) -> Result<PgnGame> {
+    for tag in SEVEN_TAG_ROSTER {
+        if game.get_tag(tag).is_none() {
+            if mode == PgnMode::Strict {
+                return Err(ChessError::ParseError(format!(
+                    "game {}: missing tag {}",
+                    index + 1,
+                    tag
+                )));
+            }
+            warnings.push(format!("game {}: missing tag {}", index + 1, tag));
+            let value = if tag == "Result" { &game.result } else { "?" };
+            game.tags.push((tag.to_string(), value.to_string()));
+        }
+    }
+    Ok(game)
+}
+
+// Attaches variations left open when the game ended to their lines
+fn close_variations(
+    tokenizer: &mut Tokenizer,
+    game: &mut PgnGame,
+    open: &mut Vec<PgnVariation>,
+) -> Result<()> {
+    if open.is_empty() {
+        return Ok(());
+    }
+    tokenizer.defect("unterminated variation")?;
+    while let Some(variation) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.variations.push(variation),
+            None => game.variations.push(variation),
+        }
+    }
+    Ok(())
+}
+
+// The games in the text, before finish_game checks their tags
+fn split_games(
+    text: &str,
+    first_line: usize,
+    mode: PgnMode,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<PgnGame>> {
+    let mut tokenizer = Tokenizer::new(text, mode, warnings);
+    tokenizer.line = first_line;
+    let mut game = PgnGame::default();
+    // variations not closed yet, the innermost last
+    let mut open = Vec::new();
+    let mut finished = Vec::new();
+
+    while let Some(token) = tokenizer.next_token()? {
+        match token {
+            Token::Tag(name, value) => {
+                close_variations(&mut tokenizer, &mut game, &mut open)?;
+                if !game.moves.is_empty() {
+                    tokenizer.defect("game without result")?;
+                    game.result = "*".to_string();
+                    finished.push(std::mem::take(&mut game));
+                }
+                game.tags.push((name, value));
+            }
+            Token::Move(san) => match open.last_mut() {
+                Some(variation) => variation.moves.push(san),
+                None => game.moves.push(san),
+            },
+            Token::VariationStart => {
+                let line = open
+                    .last()
+                    .map_or(&game.moves, |variation| &variation.moves);
+                if line.is_empty() {
+                    tokenizer.defect("variation before the first move")?;
+                }
+                open.push(PgnVariation {
+                    ply: line.len().saturating_sub(1),
+                    ..Default::default()
+                });
+            }
+            Token::VariationEnd => match open.pop() {
+                Some(variation) => match open.last_mut() {
+                    Some(parent) => parent.variations.push(variation),
+                    None => game.variations.push(variation),
+                },
+                None => tokenizer.defect("stray ')'")?,
+            },
+            Token::Result(result) => {
+                close_variations(&mut tokenizer, &mut game, &mut open)?;
+                game.result = result;
+                finished.push(std::mem::take(&mut game));
+            }
+        }
+    }
+    close_variations(&mut tokenizer, &mut game, &mut open)?;
+    if !game.tags.is_empty() || !game.moves.is_empty() {
+        tokenizer.defect("game without result")?;
+        game.result = "*".to_string();
+        finished.push(game);
+    }
+    Ok(finished)
+}
+
+fn parse_games(
+    text: &str,
+    first_line: usize,
+    first_index: usize,
+    mode: PgnMode,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<PgnGame>> {
+    split_games(text, first_line, mode, warnings)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, game)| finish_game(game, first_index + index, mode, warnings))
+        .collect()
+}
+
+// The text of one or more games, as read from the file
+struct Chunk {
+    text: Vec<u8>,
+    first_line: usize,
+    // games ended by a result, what the chunk holds as far as reading can tell
+    results: usize,
+}
+
+// Reads games one at a time, only buffering the text of the game being parsed
+pub struct PgnReader<R> {
+    reader: R,
+    mode: PgnMode,
+    line: usize,
+    games_read: usize,
+    next_line: Vec<u8>,
+    pending: VecDeque<PgnGame>,
+    warnings: Vec<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R, mode: PgnMode) -> Self {
+        PgnReader {
+            reader,
+            mode,
+            line: 1,
+            games_read: 0,
+            next_line: Vec::new(),
+            pending: VecDeque::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // Returns the text up to the tag section of the next game, or up to the
+    // blank line after a game's result so files without tags aren't read
+    // into memory whole
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        let first_line = self.line - usize::from(!self.next_line.is_empty());
+        let mut chunk = std::mem::take(&mut self.next_line);
+        let mut in_movetext = false;
+        let mut in_comment = false;
+        // variations open at the end of the line
+        let mut depth = 0usize;
+        let mut after_result = false;
+        let mut results = 0;
+
+        loop {
+            let mut line = Vec::new();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            self.line += 1;
+
+            let trimmed = line.trim_ascii();
+            if !in_comment && trimmed.starts_with(b"[") {
+                if in_movetext {
+                    self.next_line = line;
+                    break;
+                }
+                chunk.extend_from_slice(&line);
+                continue;
+            } else if trimmed.is_empty() {
+                chunk.extend_from_slice(&line);
+                if after_result {
+                    break;
+                }
+                continue;
+            } else if !trimmed.starts_with(b"%") {
+                in_movetext = true;
+            }
+            let mut end = trimmed.len();
+            for (index, &b) in trimmed.iter().enumerate() {
+                match b {
+                    b'{' => in_comment = true,
+                    b'}' => in_comment = false,
+                    b'(' if !in_comment => depth += 1,
+                    b')' if !in_comment => depth = depth.saturating_sub(1),
+                    b';' if !in_comment => {
+                        end = index;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let last_token = trimmed[..end].split(u8::is_ascii_whitespace).next_back();
+            after_result = !in_comment
+                && depth == 0
+                && last_token.is_some_and(|token| RESULTS.iter().any(|r| r.as_bytes() == token));
+            results += usize::from(after_result);
+            chunk.extend_from_slice(&line);
+        }
+
+        if chunk.trim_ascii().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Chunk {
+                text: chunk,
+                first_line,
+                results,
+            }))
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let Chunk {
+                text: chunk,
+                first_line,
+                ..
+            } = match self.read_chunk() {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let games = decode(&chunk, self.mode, &mut self.warnings).and_then(|text| {
+                parse_games(
+                    &text,
+                    first_line,
+                    self.games_read,
+                    self.mode,
+                    &mut self.warnings,
+                )
+            });
+            match games {
+                Ok(games) => {
+                    self.games_read += games.len();
+                    self.pending.extend(games);
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+// The pipe fed by the decompression thread, the thread's error is returned
+// once the pipe runs dry so a broken archive doesn't look like a short one
+#[cfg(feature = "compression")]
+struct ZipReader {
+    pipe: io::PipeReader,
+    decompressor: Option<thread::JoinHandle<io::Result<()>>>,
+}
+
+#[cfg(feature = "compression")]
+impl io::Read for ZipReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.pipe.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            if let Some(decompressor) = self.decompressor.take() {
+                decompressor
+                    .join()
+                    .map_err(|_| io::Error::other("zip decompression panicked"))??;
+            }
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "compression")]
+fn open_zip(file: File) -> io::Result<Box<dyn BufRead + Send>> {
+    use std::io::Write;
+
+    let mut archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+    let (reader, mut writer) = io::pipe()?;
+
+    // the archive entries borrow the archive, so decompress them on their own thread
+    let decompressor = thread::spawn(move || -> io::Result<()> {
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+            if entry.name().to_ascii_lowercase().ends_with(".pgn") {
+                io::copy(&mut entry, &mut writer)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    });
+
+    Ok(Box::new(BufReader::new(ZipReader {
+        pipe: reader,
+        decompressor: Some(decompressor),
+    })))
+}
+
+// Opens a PGN file, decompressing .zst, .bz2 and .zip files on the fly
+pub fn open_pgn_file(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    let name = path.to_string_lossy().to_ascii_lowercase();
+
+    #[cfg(feature = "compression")]
+    {
+        if name.ends_with(".zst") {
+            return Ok(Box::new(BufReader::new(zstd::Decoder::new(file)?)));
+        } else if name.ends_with(".bz2") {
+            return Ok(Box::new(BufReader::new(bzip2::read::MultiBzDecoder::new(
+                file,
+            ))));
+        } else if name.ends_with(".zip") {
+            return open_zip(file);
+        }
+    }
+
+    if [".zst", ".bz2", ".zip"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "compressed PGN files require the compression feature",
+        ));
+    }
+
+    Ok(Box::new(BufReader::new(file)))
+}
+
+pub fn parse_pgn(input: &[u8], mode: PgnMode) -> Result<PgnImport> {
+    let mut reader = PgnReader::new(input, mode);
+    let games = reader.by_ref().collect::<Result<Vec<PgnGame>>>()?;
+
+    Ok(PgnImport {
+        games,
+        warnings: reader.take_warnings(),
+    })
+}
+
+pub struct ImportedGame {
+    pub game: PgnGame,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ImportStats {
+    pub bytes_read: usize,
+    pub games_imported: usize,
+    pub games_rejected: usize,
+    pub warnings: Vec<String>,
+}
+
+struct ParsedChunk {
+    bytes: usize,
+    results: usize,
+    // Each game with its moves, or why they couldn't be replayed
+    games: Result<Vec<(PgnGame, Result<Vec<Move>>)>>,
+    warnings: Vec<String>,
+}
+
+// Parses and validates games on `threads` worker threads while `insert` runs
+// on the calling thread, in file order so games are numbered like PgnReader
+// does. Progress is reported in bytes read, cancelling stops reading new games.
+pub fn import_parallel<R, F>(
+    reader: R,
+    mode: PgnMode,
+    threads: usize,
+    mut insert: F,
+    progress: &ProgressHandle,
+) -> Result<ImportStats>
+where
+    R: BufRead + Send,
+    F: FnMut(ImportedGame),
+{
+    let mut pgn_reader = PgnReader::new(reader, mode);
+    let (chunk_sender, chunk_receiver) = mpsc::sync_channel(threads.max(1) * 4);
+    let chunk_receiver = Mutex::new(chunk_receiver);
+    let (parsed_sender, parsed_receiver) = mpsc::sync_channel(threads.max(1) * 4);
+    let mut stats = ImportStats::default();
+
+    thread::scope(|scope| {
+        let reader_thread = scope.spawn(move || -> Result<()> {
+            let mut index = 0;
+            while let Some(chunk) = pgn_reader.read_chunk()? {
+                if progress.is_cancelled() {
+                    break;
+                }
+                if chunk_sender.send((chunk, index)).is_err() {
+                    break;
+                }
+                index += 1;
+            }
+            Ok(())
+        });
+
+        for _ in 0..threads.max(1) {
+            let parsed_sender = parsed_sender.clone();
+            let chunk_receiver = &chunk_receiver;
+            scope.spawn(move || loop {
+                let received = chunk_receiver.lock().unwrap().recv();
+                let Ok((chunk, index)) = received else {
+                    break;
+                };
+
+                let mut warnings = Vec::new();
+                let games = decode(&chunk.text, mode, &mut warnings)
+                    .and_then(|text| split_games(&text, chunk.first_line, mode, &mut warnings))
+                    .map(|games| {
+                        games
+                            .into_iter()
+                            .map(|game| {
+                                let moves = game.validate();
+                                (game, moves)
+                            })
+                            .collect()
+                    });
+
+                let parsed = ParsedChunk {
+                    bytes: chunk.text.len(),
+                    results: chunk.results,
+                    games,
+                    warnings,
+                };
+                if parsed_sender.send((index, parsed)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(parsed_sender);
+
+        // chunks finished out of order wait here until the ones before them are in
+        let mut waiting = BTreeMap::new();
+        let mut next_chunk = 0;
+        let mut games_seen = 0;
+        for (index, parsed) in parsed_receiver {
+            waiting.insert(index, parsed);
+            while let Some(parsed) = waiting.remove(&next_chunk) {
+                next_chunk += 1;
+                stats.bytes_read += parsed.bytes;
+                stats.warnings.extend(parsed.warnings);
+                let games = match parsed.games {
+                    Ok(games) => games,
+                    Err(e) => {
+                        // every game in the chunk is lost with it
+                        let games = parsed.results.max(1);
+                        stats.games_rejected += games;
+                        let numbers = match games {
+                            1 => format!("game {}", games_seen + 1),
+                            _ => format!("games {}-{}", games_seen + 1, games_seen + games),
+                        };
+                        stats.warnings.push(format!("{}: {}", numbers, e));
+                        games_seen += games;
+                        progress.advance(parsed.bytes as u64);
+                        continue;
+                    }
+                };
+                for (game, moves) in games {
+                    // finish_game's errors already name the game
+                    let imported = finish_game(game, games_seen, mode, &mut stats.warnings)
+                        .map_err(|e| e.to_string())
+                        .and_then(|game| match moves {
+                            Ok(moves) => Ok(ImportedGame { game, moves }),
+                            Err(e) => Err(format!("game {}: {}", games_seen + 1, e)),
+                        });
+                    match imported {
+                        Ok(imported) => {
+                            insert(imported);
+                            stats.games_imported += 1;
+                        }
+                        Err(message) => {
+                            stats.games_rejected += 1;
+                            stats.warnings.push(message);
+                        }
+                    }
+                    games_seen += 1;
+                }
+                progress.advance(parsed.bytes as u64);
+            }
+        }
+
+        reader_thread.join().unwrap()
+    })?;
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(pgn_game: &PgnGame) -> String {
+        pgn_game.to_pgn(&PgnExportOptions::default())
+    }
+
+    fn parse_one(pgn: &str) -> PgnGame {
+        let mut import = parse_pgn(pgn.as_bytes(), PgnMode::Strict).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        assert_eq!(import.games.len(), 1);
+        import.games.remove(0)
+    }
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            game.push_uci(uci).unwrap();
+        }
+    }
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new();
+        // castling, a capture, a promotion and check
+        play(
+            &mut game,
+            &[
+                "e2e4", "d7d5", "e4d5", "g8f6", "g1f3", "c7c6", "f1c4", "c6c5", "e1g1", "b7b5",
+                "d5d6", "b5c4", "d6e7", "f6e4", "e7d8q",
+            ],
+        );
+        let pgn = export(&PgnGame::from_game(&game));
+        assert!(pgn.contains("5. O-O"), "{}", pgn);
+        assert!(pgn.contains("exd8=Q+"), "{}", pgn);
+
+        let parsed = parse_one(&pgn);
+        let replayed = parsed.to_game().unwrap();
+        assert_eq!(replayed.get_moves(), game.get_moves());
+        assert_eq!(replayed.to_fen(), game.to_fen());
+        assert_eq!(export(&parsed), pgn);
+    }
+
+    #[test]
+    fn result_round_trip() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"],
+        );
+        let pgn = export(&PgnGame::from_game(&game));
+        assert!(pgn.contains("4. Qxf7# 1-0"), "{}", pgn);
+        let parsed = parse_one(&pgn);
+        assert_eq!(parsed.result, "1-0");
+        assert_eq!(parsed.get_tag("Result"), Some("1-0"));
+    }
+
+    #[test]
+    fn variations_round_trip() {
+        let mut tree = GameTree::new(Game::new());
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            tree.make_move(&uci.parse().unwrap()).unwrap();
+        }
+        let after_e4 = tree.get_children(GameTree::ROOT)[0];
+        tree.go_to(after_e4);
+        for uci in ["c7c5", "g1f3"] {
+            tree.make_move(&uci.parse().unwrap()).unwrap();
+        }
+        tree.go_to(after_e4);
+        tree.make_move(&"c7c5".parse().unwrap()).unwrap();
+        tree.make_move(&"b1c3".parse().unwrap()).unwrap();
+
+        let pgn = export(&PgnGame::from_game_tree(&tree));
+        assert!(
+            pgn.contains("1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) 2. Nf3 *"),
+            "{}",
+            pgn
+        );
+        let parsed = parse_one(&pgn);
+        let replayed = parsed.to_game_tree().unwrap();
+        assert_eq!(replayed.get_main_line(), tree.get_main_line());
+        assert_eq!(export(&PgnGame::from_game_tree(&replayed)), pgn);
+    }
+
+    #[test]
+    fn chunks_end_after_results() {
+        let text = "1. e4 e5 { a blank line inside a comment\n\n} 2. Nf3 (2. f4\n\n*) 1-0\n\n\
+                    1. d4 d5 *\n\n1. c4 ; 1-0\ne5 0-1\n";
+        let mut reader = PgnReader::new(text.as_bytes(), PgnMode::Lenient);
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.read_chunk().unwrap() {
+            assert_eq!(chunk.results, 1);
+            chunks.push((
+                String::from_utf8(chunk.text).unwrap().trim().to_string(),
+                chunk.first_line,
+            ));
+        }
+        assert_eq!(chunks.len(), 3, "{:?}", chunks);
+        assert_eq!(chunks[1], ("1. d4 d5 *".to_string(), 7));
+        assert_eq!(chunks[2].1, 9);
+        assert!(chunks[0].0.ends_with("1-0"));
+    }
+
+    #[test]
+    fn a_broken_chunk_rejects_every_game_in_it() {
+        let tags = SEVEN_TAG_ROSTER
+            .map(|tag| format!("[{} \"?\"]\n", tag))
+            .concat();
+        let text =
+            format!("{tags}\n1. e4 e5 1-0\n1. d4 d5 1-0\n1. c4 ) e5 0-1\n\n{tags}\n1. Nf3 *\n")
+                .replace("[Result \"?\"]", "[Result \"*\"]");
+        let mut imported = Vec::new();
+        let stats = import_parallel(
+            text.as_bytes(),
+            PgnMode::Strict,
+            2,
+            |game| imported.push(game),
+            &ProgressHandle::new(),
+        )
+        .unwrap();
+        assert_eq!(stats.games_rejected, 3);
+        assert_eq!(stats.games_imported, 1);
+        assert!(
+            stats.warnings[0].starts_with("games 1-3: "),
+            "{:?}",
+            stats.warnings
+        );
+        assert_eq!(imported[0].game.moves, ["Nf3"]);
+    }
+
+    #[test]
+    fn long_algebraic_and_iccf_moves_are_read() {
+        let tags = SEVEN_TAG_ROSTER
+            .map(|tag| format!("[{} \"?\"]\n", tag))
+            .concat();
+        let pgn = format!("{tags}\n1. e2-e4 e7-e5 2. Ng1-f3 2836 (2... Nb8xc6) 3. 6134 *\n")
+            .replace("[Result \"?\"]", "[Result \"*\"]");
+        let pgn_game = parse_one(&pgn);
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4"]);
+        assert_eq!(pgn_game.validate().unwrap(), game.get_moves());
+        assert_eq!(pgn_game.to_game().unwrap().to_fen(), game.to_fen());
+        assert_eq!(
+            pgn_game.to_game_tree().unwrap().get_main_line(),
+            game.get_moves()
+        );
+
+        let mut bad = pgn_game.clone();
+        bad.moves[3] = "2837".to_string();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn set_up_position_round_trip() {
+        let fen = "4k3/8/8/8/8/8/7p/R3K3 b Q - 3 40";
+        let mut game = Game::from_fen(fen).unwrap();
+        play(&mut game, &["e8d7", "e1c1"]);
+        let pgn = export(&PgnGame::from_game(&game));
+        assert!(pgn.contains(&format!("[FEN \"{}\"]", fen)), "{}", pgn);
+        assert!(pgn.contains("40... Kd7 41. O-O-O+"), "{}", pgn);
+
+        let replayed = parse_one(&pgn).to_game().unwrap();
+        assert_eq!(replayed.get_starting_fen(), fen);
+        assert_eq!(replayed.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn only_standard_chess_is_played() {
+        let mut pgn_game = PgnGame::from_game(&Game::new());
+        pgn_game.moves = vec!["e4".to_string()];
+        for (variant, played) in [
+            ("Standard", true),
+            ("From Position", true),
+            ("chess960", false),
+            ("Crazyhouse", false),
+        ] {
+            pgn_game.tags.retain(|(tag, _)| tag != "Variant");
+            pgn_game
+                .tags
+                .push(("Variant".to_string(), variant.to_string()));
+            assert_eq!(pgn_game.to_game().is_ok(), played, "{}", variant);
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let pgn = "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                   [White \"?\"]\n[Black \"?\"]\n[Result \"*\"]\n\n1. e4 e5 2. Ke3 *\n";
+        let parsed = parse_one(pgn);
+        assert!(parsed.to_game().is_err());
+        assert!(parsed.validate().is_err());
+
+        // the game is drawn once only the kings are left
+        let mut drawn = parsed.clone();
+        drawn.tags.push((
+            "FEN".to_string(),
+            "4k3/8/8/8/8/8/8/3rK3 w - - 0 1".to_string(),
+        ));
+        drawn.moves = ["Kxd1", "Kd7", "Ke2"].map(String::from).to_vec();
+        assert!(matches!(drawn.validate(), Err(ChessError::GameOver)));
+    }
+
+    #[test]
+    fn annotations_apart_from_their_move() {
+        let pgn = "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                   [White \"?\"]\n[Black \"?\"]\n[Result \"*\"]\n\n1. e4 !? e5 ?? 2. Nf3! $1 ! *\n";
+        let parsed = parse_one(pgn);
+        assert_eq!(parsed.moves, ["e4", "e5", "Nf3!"]);
+        assert_eq!(parsed.validate().unwrap().len(), 3);
+    }
+}
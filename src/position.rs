@@ -1,4 +1,6 @@
-use super::{MoveError, Result};
+use super::{ChessError, Result};
+use std::{fmt, str::FromStr};
+
 #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct Position {
     row: usize,
@@ -9,7 +11,10 @@ impl Position {
         if row < 8 && column < 8 {
             Ok(Self { row, column })
         } else {
-            Err(MoveError)
+            Err(ChessError::OutOfBounds {
+                row: row as isize,
+                column: column as isize,
+            })
         }
     }
 
@@ -28,7 +33,10 @@ impl Position {
         if new_row >= 0 && new_col >= 0 {
             Self::try_new(new_row as usize, new_col as usize)
         } else {
-            Err(MoveError)
+            Err(ChessError::OutOfBounds {
+                row: new_row,
+                column: new_col,
+            })
         }
     }
 
@@ -179,3 +187,49 @@ impl Position {
             .collect::<Vec<Position>>()
     }
 }
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.column as u8) as char,
+            (b'1' + self.row as u8) as char
+        )
+    }
+}
+
+impl FromStr for Position {
+    type Err = ChessError;
+
+    // Parses a square in algebraic notation, e.g. "e4"
+    fn from_str(s: &str) -> Result<Self> {
+        match s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Self::try_new((rank - b'1') as usize, (file - b'a') as usize)
+            }
+            _ => Err(ChessError::ParseError(format!("invalid square {:?}", s))),
+        }
+    }
+}
+
+// Squares are stored as their name, which can't be out of bounds
+#[cfg(feature = "serde")]
+impl serde::Serialize for Position {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Position {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
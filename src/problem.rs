@@ -0,0 +1,251 @@
+use super::{
+    epd::{EpdOperation, EpdRecord},
+    notation,
+    progress::ProgressHandle,
+    Board, ChessError, Color, Game, GameStatus, Move, PromotionPiece, Result,
+};
+
+// What the side to move has to achieve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stipulation {
+    // Mate in at most this many moves against any defence
+    MateIn(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Soundness {
+    // A single key move solves the problem
+    Sound(Move),
+    NoSolution,
+    // Every key move that works, a sound problem has only one
+    Cooked(Vec<Move>),
+}
+
+// A composed position together with its stipulation
+#[derive(Clone)]
+pub struct Problem {
+    game: Game,
+    stipulation: Stipulation,
+}
+
+// The moves are made and taken back on a single board, a Game would check
+// each of them and work out the game's status again at every node
+struct Search<'a> {
+    board: Board,
+    promotions: &'a [PromotionPiece],
+    progress: &'a ProgressHandle,
+}
+
+impl Search<'_> {
+    fn get_legal_moves(&self, color: Color) -> Vec<Move> {
+        let mut moves = self.board.get_all_available_moves(color);
+        moves.retain(|mv| {
+            mv.get_promotion()
+                .is_none_or(|promotion| self.promotions.contains(&promotion))
+        });
+        moves
+    }
+
+    // Whether playing the move mates within `moves` moves against any
+    // defence. The search is exhaustive, which is fine for the short mates
+    // problems use, and too short to run into a draw by repetition or the
+    // fifty-move rule. Nothing mates once the search is cancelled.
+    fn is_key_move(&mut self, color: Color, mv: &Move, moves: u32) -> bool {
+        if moves == 0 || self.progress.is_cancelled() {
+            return false;
+        }
+        let defender = color.switch();
+        let undo = (self.board.castling_rights, self.board.en_passant);
+        self.board.apply_move(mv);
+        let defences = self.get_legal_moves(defender);
+        let mates = if defences.is_empty() {
+            matches!(self.board.is_king_in_check(defender), Ok(Some(_)))
+        } else {
+            moves > 1
+                && defences.iter().all(|defence| {
+                    let undo = (self.board.castling_rights, self.board.en_passant);
+                    self.board.apply_move(defence);
+                    let mated = self
+                        .get_legal_moves(color)
+                        .iter()
+                        .any(|attack| self.is_key_move(color, attack, moves - 1));
+                    self.board.unmake_move(defence, undo.0, undo.1);
+                    mated
+                })
+        };
+        self.board.unmake_move(mv, undo.0, undo.1);
+        mates
+    }
+}
+
+impl Problem {
+    // The problem starts from the game's current position
+    pub fn new(game: Game, stipulation: Stipulation) -> Self {
+        Problem { game, stipulation }
+    }
+
+    // Reads the stipulation from the record's `dm` operation
+    pub fn from_epd(record: &EpdRecord) -> Result<Self> {
+        let moves = record
+            .get_operation("dm")
+            .and_then(|operands| operands.first())
+            .ok_or_else(|| ChessError::ParseError("EPD record without dm".to_string()))?;
+        let moves = moves
+            .parse()
+            .map_err(|_| ChessError::ParseError(format!("invalid dm {:?}", moves)))?;
+        Ok(Problem::new(record.to_game()?, Stipulation::MateIn(moves)))
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_stipulation(&self) -> Stipulation {
+        self.stipulation
+    }
+
+    // Every first move that meets the stipulation whatever the defence
+    pub fn solve(&self) -> Vec<Move> {
+        // a new handle is never cancelled
+        self.solve_with_progress(&ProgressHandle::new()).unwrap()
+    }
+
+    // Progress is counted in first moves tried, None once cancelled
+    pub fn solve_with_progress(&self, progress: &ProgressHandle) -> Option<Vec<Move>> {
+        let Stipulation::MateIn(moves) = self.stipulation;
+        if !matches!(self.game.status(), GameStatus::Ongoing | GameStatus::Check) {
+            return Some(Vec::new());
+        }
+        let color = self.game.get_turn();
+        let mut search = Search {
+            board: self.game.get_board().clone(),
+            promotions: self.game.get_allowed_promotions(),
+            progress,
+        };
+        let first_moves = search.get_legal_moves(color);
+        progress.set_total(first_moves.len() as u64);
+        let mut keys = Vec::new();
+        for mv in first_moves {
+            if search.is_key_move(color, &mv, moves) {
+                keys.push(mv);
+            }
+            if progress.is_cancelled() {
+                return None;
+            }
+            progress.advance(1);
+        }
+        Some(keys)
+    }
+
+    pub fn check_soundness(&self) -> Soundness {
+        self.check_soundness_with_progress(&ProgressHandle::new())
+            .unwrap()
+    }
+
+    pub fn check_soundness_with_progress(&self, progress: &ProgressHandle) -> Option<Soundness> {
+        let mut keys = self.solve_with_progress(progress)?;
+        Some(match keys.len() {
+            0 => Soundness::NoSolution,
+            1 => Soundness::Sound(keys.remove(0)),
+            _ => Soundness::Cooked(keys),
+        })
+    }
+
+    // The stipulation goes in `dm` and the key move in `bm`, None unless the
+    // problem is sound
+    pub fn to_epd(&self) -> Option<EpdRecord> {
+        self.soundness_to_epd(&self.check_soundness())
+    }
+
+    // to_epd for a soundness check_soundness already found for this problem,
+    // so a long search doesn't have to run twice
+    pub fn soundness_to_epd(&self, soundness: &Soundness) -> Option<EpdRecord> {
+        let Soundness::Sound(key) = soundness else {
+            return None;
+        };
+        let Stipulation::MateIn(moves) = self.stipulation;
+        let board = self.game.get_board();
+        Some(EpdRecord {
+            board: board.clone(),
+            turn: self.game.get_turn(),
+            operations: vec![
+                EpdOperation {
+                    opcode: "dm".to_string(),
+                    operands: vec![moves.to_string()],
+                },
+                EpdOperation {
+                    opcode: "bm".to_string(),
+                    operands: vec![notation::move_to_san(board, key).ok()?],
+                },
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(fen: &str, moves: u32) -> Problem {
+        Problem::new(Game::from_fen(fen).unwrap(), Stipulation::MateIn(moves))
+    }
+
+    fn keys(problem: &Problem) -> Vec<String> {
+        problem.solve().iter().map(Move::to_uci).collect()
+    }
+
+    #[test]
+    fn sound_problem() {
+        // a quiet key, Kc7 Ka7 Ra1#
+        let sound = problem("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 2);
+        assert_eq!(keys(&sound), ["c6c7"]);
+        assert!(matches!(sound.check_soundness(), Soundness::Sound(key) if key.to_uci() == "c6c7"));
+        assert_eq!(
+            sound.to_epd().unwrap().to_string(),
+            "k7/8/2K5/8/8/8/8/1R6 w - - dm 2; bm Kc7;"
+        );
+
+        // mate in one counts as mate in at most two
+        let back_rank = problem("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!(keys(&back_rank), ["a1a8"]);
+        // Qg8+ Rxg8 Nf7#
+        let smothered = problem("r6k/6pp/7N/8/8/1Q6/8/6K1 w - - 0 1", 2);
+        assert_eq!(keys(&smothered), ["b3g8"]);
+    }
+
+    #[test]
+    fn cooked_problem() {
+        let cooked = problem("7k/5K2/8/8/8/8/8/6R1 w - - 0 1", 2);
+        let Soundness::Cooked(keys) = cooked.check_soundness() else {
+            panic!("not cooked");
+        };
+        // most rook moves mate on the h-file next
+        assert_eq!(keys.len(), 11);
+        assert!(keys.iter().all(|key| key.get_from().to_string() == "g1"));
+        assert!(cooked.to_epd().is_none());
+    }
+
+    #[test]
+    fn no_solution() {
+        for (fen, moves) in [
+            ("kb6/p7/8/1K6/8/8/8/2R5 w - - 0 1", 2),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 3),
+            // already mate
+            ("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", 1),
+        ] {
+            assert_eq!(problem(fen, moves).check_soundness(), Soundness::NoSolution);
+        }
+    }
+
+    #[test]
+    fn cancelled_search() {
+        let progress = ProgressHandle::new();
+        progress.cancel();
+        let problem = problem("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 2);
+        assert_eq!(problem.check_soundness_with_progress(&progress), None);
+
+        let progress = ProgressHandle::new();
+        problem.solve_with_progress(&progress).unwrap();
+        assert_eq!(progress.get_fraction(), Some(1.0));
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    done: AtomicU64,
+    total: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+// Shared between a long-running operation and whoever displays it, clones
+// refer to the same progress
+#[derive(Debug, Default, Clone)]
+pub struct ProgressHandle {
+    state: Arc<ProgressState>,
+}
+
+impl ProgressHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.state.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, amount: u64) {
+        self.state.done.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn get_done(&self) -> u64 {
+        self.state.done.load(Ordering::Relaxed)
+    }
+
+    // None while the total amount of work is unknown
+    pub fn get_fraction(&self) -> Option<f32> {
+        let total = self.state.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return None;
+        }
+        Some((self.get_done() as f64 / total as f64).min(1.0) as f32)
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,236 @@
+use super::pgn::ImportedGame;
+use super::Color;
+use std::fmt::Write;
+
+// Number of plies used to tell openings apart
+const OPENING_PLIES: usize = 6;
+// Lost games shorter than these ply counts are counted as short/medium losses
+const SHORT_GAME_END: usize = 30;
+const MEDIUM_GAME_END: usize = 80;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OpeningRecord {
+    pub line: String,
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PlayerReport {
+    pub player: String,
+    pub games: usize,
+    pub white_openings: Vec<OpeningRecord>,
+    pub black_openings: Vec<OpeningRecord>,
+    // Losses grouped by how long the game lasted: short, medium and long
+    // games. Only the length is known, not where the game was lost.
+    pub losses_by_length: [usize; 3],
+}
+
+// `score` is counted in half points: 2 for a win, 1 for a draw, 0 for a loss
+fn add_game(openings: &mut Vec<OpeningRecord>, line: String, score: Option<u8>) {
+    let index = match openings.iter().position(|record| record.line == line) {
+        Some(index) => index,
+        None => {
+            openings.push(OpeningRecord {
+                line,
+                ..Default::default()
+            });
+            openings.len() - 1
+        }
+    };
+    let record = &mut openings[index];
+    record.games += 1;
+    match score {
+        Some(2) => record.wins += 1,
+        Some(1) => record.draws += 1,
+        Some(_) => record.losses += 1,
+        None => {}
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl PlayerReport {
+    pub fn new<'a, I>(player: &str, games: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ImportedGame>,
+    {
+        let mut report = PlayerReport {
+            player: player.to_string(),
+            ..Default::default()
+        };
+
+        for imported in games {
+            let game = &imported.game;
+            let color = if game.get_tag("White") == Some(player) {
+                Color::White
+            } else if game.get_tag("Black") == Some(player) {
+                Color::Black
+            } else {
+                continue;
+            };
+
+            let score = match (game.result.as_str(), color) {
+                ("1-0", Color::White) | ("0-1", Color::Black) => Some(2),
+                ("0-1", Color::White) | ("1-0", Color::Black) => Some(0),
+                ("1/2-1/2", _) => Some(1),
+                _ => None,
+            };
+
+            let line = game
+                .moves
+                .iter()
+                .take(OPENING_PLIES)
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(" ");
+            let openings = match color {
+                Color::White => &mut report.white_openings,
+                Color::Black => &mut report.black_openings,
+            };
+            add_game(openings, line, score);
+
+            if score == Some(0) {
+                let length = match imported.moves.len() {
+                    plies if plies < SHORT_GAME_END => 0,
+                    plies if plies < MEDIUM_GAME_END => 1,
+                    _ => 2,
+                };
+                report.losses_by_length[length] += 1;
+            }
+            report.games += 1;
+        }
+
+        report
+            .white_openings
+            .sort_by_key(|record| std::cmp::Reverse(record.games));
+        report
+            .black_openings
+            .sort_by_key(|record| std::cmp::Reverse(record.games));
+        report
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let player = escape_html(&self.player);
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+        writeln!(
+            html,
+            "<title>Preparation report: {}</title></head><body>",
+            player
+        )
+        .unwrap();
+        writeln!(html, "<h1>{}</h1>", player).unwrap();
+        writeln!(html, "<p>{} games in the database</p>", self.games).unwrap();
+
+        for (title, openings) in [
+            ("Openings as White", &self.white_openings),
+            ("Openings as Black", &self.black_openings),
+        ] {
+            writeln!(html, "<h2>{}</h2>", title).unwrap();
+            writeln!(
+                html,
+                "<table><tr><th>Line</th><th>Games</th><th>+</th><th>=</th><th>-</th></tr>"
+            )
+            .unwrap();
+            for record in openings {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&record.line),
+                    record.games,
+                    record.wins,
+                    record.draws,
+                    record.losses
+                )
+                .unwrap();
+            }
+            writeln!(html, "</table>").unwrap();
+        }
+
+        writeln!(html, "<h2>Losses by game length</h2><ul>").unwrap();
+        let lengths = [
+            format!("Under {} moves", SHORT_GAME_END / 2),
+            format!(
+                "{} to {} moves",
+                SHORT_GAME_END / 2,
+                MEDIUM_GAME_END / 2 - 1
+            ),
+            format!("{} moves or more", MEDIUM_GAME_END / 2),
+        ];
+        for (length, losses) in lengths.iter().zip(self.losses_by_length) {
+            writeln!(html, "<li>{}: {}</li>", length, losses).unwrap();
+        }
+        writeln!(html, "</ul></body></html>").unwrap();
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::{PgnMode, PgnReader};
+
+    fn imported(white: &str, black: &str, result: &str, movetext: &str) -> ImportedGame {
+        let pgn = format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n\n{} {}\n",
+            white, black, result, movetext, result
+        );
+        let game = PgnReader::new(pgn.as_bytes(), PgnMode::Lenient)
+            .next()
+            .unwrap()
+            .unwrap();
+        let moves = game.validate().unwrap();
+        ImportedGame { game, moves }
+    }
+
+    #[test]
+    fn openings_and_losses_are_counted() {
+        let quiet = "1. Nf3 Nf6 2. Nc3 Nc6 3. e3 e6 4. Be2 Be7 5. O-O O-O 6. d3 d6 \
+                     7. Bd2 Bd7 8. Qe1 Qe8 9. a3 a6 10. b3 b6 11. h3 h6 12. g3 g6 \
+                     13. Kg2 Kg7 14. Rh1 Rh8 15. Rb1 Rb8 16. Qf1 Qf8";
+        let games = [
+            imported("Anna", "Ben", "0-1", "1. f3 e5 2. g4 Qh4#"),
+            imported("Anna", "Carl", "1-0", "1. f3 e5 2. g4 d5 3. e4"),
+            imported("Ben", "Anna", "1-0", quiet),
+            imported("Ben", "Anna", "1/2-1/2", quiet),
+            imported("Ben", "Carl", "1-0", "1. e4"),
+        ];
+        let report = PlayerReport::new("Anna", &games);
+        assert_eq!(report.games, 4);
+
+        let record = |line: &str, wins, draws, losses| OpeningRecord {
+            line: line.to_string(),
+            games: wins + draws + losses,
+            wins,
+            draws,
+            losses,
+        };
+        assert_eq!(
+            report.white_openings,
+            [
+                record("f3 e5 g4 Qh4#", 0, 0, 1),
+                record("f3 e5 g4 d5 e4", 1, 0, 0)
+            ]
+        );
+        assert_eq!(
+            report.black_openings,
+            [record("Nf3 Nf6 Nc3 Nc6 e3 e6", 0, 1, 1)]
+        );
+
+        // a four ply loss and a 32 ply one
+        assert_eq!(report.losses_by_length, [1, 1, 0]);
+        let html = report.to_html();
+        assert!(html.contains("<li>Under 15 moves: 1</li>"), "{}", html);
+        assert!(html.contains("<li>15 to 39 moves: 1</li>"), "{}", html);
+        assert!(html.contains("<li>40 moves or more: 0</li>"), "{}", html);
+    }
+}
@@ -0,0 +1,314 @@
+use super::{
+    fen, Bishop, Board, CastlingSide, ChessError, ChessPiece, ChessPieceType, Color, King, Knight,
+    Move, Pawn, Position, PromotionPiece, Queen, Result, Rook,
+};
+
+const POSITION_PREFIX: &str = "chess-rs://position/";
+const GAME_PREFIX: &str = "chess-rs://game/";
+const CASTLING_BITS: [(Color, CastlingSide); 4] = [
+    (Color::White, CastlingSide::KingSide),
+    (Color::White, CastlingSide::QueenSide),
+    (Color::Black, CastlingSide::KingSide),
+    (Color::Black, CastlingSide::QueenSide),
+];
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub enum SharedLink {
+    Position(Board, Color),
+    Game(Vec<Move>),
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64_URL[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for c in encoded.bytes() {
+        let value = BASE64_URL
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| ChessError::ParseError("invalid character in link".to_string()))?;
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+fn piece_to_nibble(piece: &Option<ChessPiece>) -> u8 {
+    match piece {
+        None => 0,
+        Some(piece) => {
+            let kind = match piece.chess_piece {
+                ChessPieceType::Pawn(_) => 1,
+                ChessPieceType::Knight(_) => 2,
+                ChessPieceType::Bishop(_) => 3,
+                ChessPieceType::Rook(_) => 4,
+                ChessPieceType::Queen(_) => 5,
+                ChessPieceType::King(_) => 6,
+            };
+            match piece.color {
+                Color::White => kind,
+                Color::Black => kind | 8,
+            }
+        }
+    }
+}
+
+fn nibble_to_piece(nibble: u8) -> Result<Option<ChessPiece>> {
+    let chess_piece = match nibble & 7 {
+        0 => return Ok(None),
+        1 => ChessPieceType::Pawn(Pawn),
+        2 => ChessPieceType::Knight(Knight),
+        3 => ChessPieceType::Bishop(Bishop),
+        4 => ChessPieceType::Rook(Rook),
+        5 => ChessPieceType::Queen(Queen),
+        6 => ChessPieceType::King(King),
+        _ => return Err(ChessError::ParseError("invalid piece in link".to_string())),
+    };
+    let color = if nibble & 8 == 0 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Ok(Some(ChessPiece::new(chess_piece, color)))
+}
+
+// Two nibbles per rank-major pair of squares, followed by the side to move in
+// the lowest bit and the castling rights in the next four. When a pawn can be
+// taken en passant, a last byte holds the file of the square it skipped.
+pub fn encode_position(board: &Board, turn: Color) -> String {
+    let mut bytes = board
+        .squares
+        .iter()
+        .flatten()
+        .collect::<Vec<&Option<ChessPiece>>>()
+        .chunks(2)
+        .map(|pair| piece_to_nibble(pair[0]) << 4 | piece_to_nibble(pair[1]))
+        .collect::<Vec<u8>>();
+    let castling = CASTLING_BITS
+        .iter()
+        .enumerate()
+        .filter(|(_, &(color, side))| board.castling_rights.can_castle(color, side))
+        .fold(0u8, |bits, (i, _)| bits | 2 << i);
+    bytes.push(u8::from(turn == Color::Black) | castling);
+    if let Some(en_passant) = board.en_passant {
+        bytes.push(en_passant.get_column() as u8);
+    }
+    format!("{}{}", POSITION_PREFIX, encode_base64(&bytes))
+}
+
+// 12 bits per move packed into two bytes, the spare high bits hold the
+// promotion piece
+pub fn encode_game(moves: &[Move]) -> String {
+    let bytes = moves
+        .iter()
+        .flat_map(|mv| {
+            let from = mv.get_from().get_row() * 8 + mv.get_from().get_column();
+            let to = mv.get_to().get_row() * 8 + mv.get_to().get_column();
+            let promotion = mv.get_promotion().map_or(0, |promotion| {
+                PromotionPiece::ALL
+                    .iter()
+                    .position(|&p| p == promotion)
+                    .unwrap()
+                    + 1
+            });
+            ((promotion << 12 | from << 6 | to) as u16).to_be_bytes()
+        })
+        .collect::<Vec<u8>>();
+    format!("{}{}", GAME_PREFIX, encode_base64(&bytes))
+}
+
+pub fn parse_link(link: &str) -> Result<SharedLink> {
+    if let Some(data) = link.strip_prefix(POSITION_PREFIX) {
+        let bytes = decode_base64(data)?;
+        if !matches!(bytes.len(), 33 | 34) {
+            return Err(ChessError::ParseError(
+                "position link has the wrong length".to_string(),
+            ));
+        }
+        let mut board = Board::new();
+        for (i, byte) in bytes[..32].iter().enumerate() {
+            board.set_square(i / 4, i % 4 * 2, nibble_to_piece(byte >> 4)?);
+            board.set_square(i / 4, i % 4 * 2 + 1, nibble_to_piece(byte & 0xF)?);
+        }
+        if bytes[32] >> 5 != 0 {
+            return Err(ChessError::ParseError(
+                "unknown flags in position link".to_string(),
+            ));
+        }
+        let turn = match bytes[32] & 1 {
+            0 => Color::White,
+            _ => Color::Black,
+        };
+        for (i, &(color, side)) in CASTLING_BITS.iter().enumerate() {
+            board
+                .castling_rights
+                .set(color, side, bytes[32] & 2 << i != 0);
+        }
+        if let Some(&file) = bytes.get(33) {
+            let row = match turn {
+                Color::White => 5,
+                Color::Black => 2,
+            };
+            board.en_passant = Some(Position::try_new(row, file as usize)?);
+        }
+        // links are held to the same rules as FEN, so they can't set up a
+        // position the board can't play from
+        let position = fen::parse_fen(&fen::to_fen(&board, turn, 0, 1))?;
+        Ok(SharedLink::Position(position.board, position.turn))
+    } else if let Some(data) = link.strip_prefix(GAME_PREFIX) {
+        let bytes = decode_base64(data)?;
+        if bytes.len() % 2 != 0 {
+            return Err(ChessError::ParseError(
+                "game link has the wrong length".to_string(),
+            ));
+        }
+        bytes
+            .chunks(2)
+            .map(|pair| {
+                let packed = u16::from_be_bytes([pair[0], pair[1]]) as usize;
+                let from = Position::try_new(packed >> 9 & 7, packed >> 6 & 7)?;
+                let to = Position::try_new(packed >> 3 & 7, packed & 7)?;
+                match packed >> 12 {
+                    0 => Ok(Move::new(from, to)),
+                    promotion => Ok(Move::with_promotion(
+                        from,
+                        to,
+                        *PromotionPiece::ALL.get(promotion - 1).ok_or_else(|| {
+                            ChessError::ParseError("invalid promotion in game link".to_string())
+                        })?,
+                    )),
+                }
+            })
+            .collect::<Result<Vec<Move>>>()
+            .map(SharedLink::Game)
+    } else {
+        Err(ChessError::ParseError("unknown link type".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position_of(link: &str) -> Result<String> {
+        match parse_link(link)? {
+            SharedLink::Position(board, turn) => Ok(fen::to_fen(&board, turn, 0, 1)),
+            SharedLink::Game(_) => panic!("{} isn't a position link", link),
+        }
+    }
+
+    fn link_for(fen: &str) -> String {
+        let position = fen::parse_fen(fen).unwrap();
+        encode_position(&position.board, position.turn)
+    }
+
+    fn position_link(bytes: &[u8]) -> String {
+        format!("{}{}", POSITION_PREFIX, encode_base64(bytes))
+    }
+
+    #[test]
+    fn position_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1",
+        ] {
+            assert_eq!(position_of(&link_for(fen)).unwrap(), fen);
+        }
+    }
+
+    #[test]
+    fn game_round_trip() {
+        let moves = [
+            "e2e4".parse().unwrap(),
+            "g1f3".parse().unwrap(),
+            "a7a8n".parse().unwrap(),
+            "h2h1q".parse().unwrap(),
+        ];
+        let Ok(SharedLink::Game(parsed)) = parse_link(&encode_game(&moves)) else {
+            panic!("game link didn't parse");
+        };
+        assert_eq!(parsed.len(), moves.len());
+        for (parsed, mv) in parsed.iter().zip(&moves) {
+            assert_eq!(parsed.get_from(), mv.get_from());
+            assert_eq!(parsed.get_to(), mv.get_to());
+            assert_eq!(parsed.get_promotion(), mv.get_promotion());
+        }
+    }
+
+    #[test]
+    fn malformed_links() {
+        let start = link_for("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut bytes = decode_base64(start.strip_prefix(POSITION_PREFIX).unwrap()).unwrap();
+        let truncated = position_link(&bytes[..32]);
+        let too_long = position_link(&[bytes.as_slice(), &[0, 0]].concat());
+        bytes[32] |= 0x80;
+        let unknown_flags = position_link(&bytes);
+        for link in [
+            "https://example.com/position/AAAA".to_string(),
+            format!("{}not base64!", POSITION_PREFIX),
+            truncated,
+            too_long,
+            unknown_flags,
+            format!("{}AA", GAME_PREFIX),
+            format!("{}{}", GAME_PREFIX, encode_base64(&[0x70, 0x00])),
+        ] {
+            assert!(
+                matches!(parse_link(&link), Err(ChessError::ParseError(_))),
+                "{}",
+                link
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_positions_in_links() {
+        let mut board = Board::new();
+        board.squares[0][4] = Some(ChessPiece::new(ChessPieceType::King(King), Color::White));
+        board.squares[7][4] = Some(ChessPiece::new(ChessPieceType::King(King), Color::Black));
+        board.squares[1][4] = Some(ChessPiece::new(ChessPieceType::Rook(Rook), Color::White));
+
+        // black is in check with white to move
+        let in_check = encode_position(&board, Color::White);
+        // castling without a rook in the corner
+        board
+            .castling_rights
+            .set(Color::Black, CastlingSide::KingSide, true);
+        let castling = encode_position(&board, Color::Black);
+        board.castling_rights = Default::default();
+        // no pawn skipped the square
+        board.en_passant = Some("e3".parse().unwrap());
+        let en_passant = encode_position(&board, Color::Black);
+        board.en_passant = None;
+        board.squares[7][4] = None;
+        let missing_king = encode_position(&board, Color::Black);
+
+        for link in [in_check, castling, en_passant, missing_king] {
+            assert!(
+                matches!(parse_link(&link), Err(ChessError::InvalidPosition(_))),
+                "{}",
+                link
+            );
+        }
+        assert!(position_of(&encode_position(&board, Color::Black)).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use super::{ChessPieceType, Game, GameStatus, Move};
+use std::fmt;
+use std::ops::Range;
+
+// Long enough for almost every random game to end by a draw rule first
+const MAX_PLIES: usize = 1000;
+
+// xorshift64*, kept in-house so a seed replays the same game on every
+// platform and dependency version
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // zero is a fixed point of xorshift
+        Rng(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub moves: Vec<Move>,
+    pub status: GameStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct InvariantViolation {
+    pub seed: u64,
+    pub ply: usize,
+    pub message: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "seed {}, ply {}: {}", self.seed, self.ply, self.message)
+    }
+}
+
+// Plays uniformly random legal moves until the game ends
+pub fn simulate_random_game(seed: u64) -> GameRecord {
+    let mut rng = Rng::new(seed);
+    let mut game = Game::new();
+    while game.get_ply_count() < MAX_PLIES {
+        let moves = game.get_board().get_all_available_moves(game.get_turn());
+        if moves.is_empty() {
+            break;
+        }
+        let mv = moves[(rng.next() % moves.len() as u64) as usize];
+        if game.make_move(&mv).is_err() {
+            break;
+        }
+    }
+    GameRecord {
+        seed,
+        status: game.status(),
+        moves: game.get_moves().to_vec(),
+    }
+}
+
+// Replays the game move by move and checks the rules hold at every ply
+fn check_record(record: &GameRecord) -> Result<(), InvariantViolation> {
+    let violation = |ply, message: &str| InvariantViolation {
+        seed: record.seed,
+        ply,
+        message: message.to_string(),
+    };
+
+    let mut game = Game::new();
+    for (ply, mv) in record.moves.iter().enumerate() {
+        if !matches!(game.status(), GameStatus::Ongoing | GameStatus::Check) {
+            return Err(violation(ply, "a move was played after the game ended"));
+        }
+        if mv
+            .get_captured()
+            .is_some_and(|piece| matches!(piece.get_type(), ChessPieceType::King(_)))
+        {
+            return Err(violation(ply, "a king was captured"));
+        }
+
+        let before = game.get_board().clone();
+        let mover = game.get_turn();
+        if game.make_move(mv).is_err() {
+            return Err(violation(ply, "a recorded move was rejected on replay"));
+        }
+        match game.get_board().is_king_in_check(mover) {
+            Ok(None) => {}
+            Ok(Some(_)) => return Err(violation(ply, "a move left its own king in check")),
+            Err(_) => return Err(violation(ply, "a move lost its own king")),
+        }
+
+        let mut undone = game.clone();
+        undone.unmake_move();
+        if *undone.get_board() != before || undone.get_turn() != mover {
+            return Err(violation(ply, "unmake_move didn't restore the position"));
+        }
+    }
+
+    if game.status() != record.status {
+        return Err(violation(
+            record.moves.len(),
+            "the final status changed on replay",
+        ));
+    }
+    if matches!(record.status, GameStatus::Ongoing | GameStatus::Check)
+        && record.moves.len() < MAX_PLIES
+    {
+        return Err(violation(
+            record.moves.len(),
+            "the game stopped while still going",
+        ));
+    }
+    Ok(())
+}
+
+// Returns the first broken invariant over all the seeds
+pub fn check_random_games(seeds: Range<u64>) -> Result<usize, InvariantViolation> {
+    let mut games = 0;
+    for seed in seeds {
+        check_record(&simulate_random_game(seed))?;
+        games += 1;
+    }
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_replay_the_same_game() {
+        let first = simulate_random_game(7);
+        let again = simulate_random_game(7);
+        assert_eq!(first.moves, again.moves);
+        assert_eq!(first.status, again.status);
+        assert_ne!(simulate_random_game(8).moves, first.moves);
+    }
+
+    #[test]
+    fn random_games_keep_the_rules() {
+        assert_eq!(check_random_games(0..10).unwrap(), 10);
+    }
+
+    #[test]
+    fn broken_records_are_reported() {
+        let record = simulate_random_game(3);
+
+        let mut cut = record.clone();
+        cut.moves.truncate(10);
+        let violation = check_record(&cut).unwrap_err();
+        assert_eq!(violation.ply, 10);
+        assert_eq!(violation.seed, 3);
+
+        let mut swapped = record.clone();
+        swapped.moves.swap(0, 1);
+        assert_eq!(check_record(&swapped).unwrap_err().ply, 0);
+    }
+}
@@ -0,0 +1,80 @@
+use super::{Board, CastlingSide, ChessPieceType, Color};
+
+// One random key per piece kind, color and square, then the castling rights,
+// the en passant column and the side to move
+const PIECE_KEYS: usize = 6 * 2 * 64;
+const CASTLING_KEYS: usize = 4;
+const EN_PASSANT_KEYS: usize = 8;
+const KEY_COUNT: usize = PIECE_KEYS + CASTLING_KEYS + EN_PASSANT_KEYS + 1;
+
+// Generated at compile time so hashes are the same across runs and platforms
+const KEYS: [u64; KEY_COUNT] = {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        // xorshift64*
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        keys[i] = state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        i += 1;
+    }
+    keys
+};
+
+fn piece_index(piece: &ChessPieceType) -> usize {
+    match piece {
+        ChessPieceType::Pawn(_) => 0,
+        ChessPieceType::Knight(_) => 1,
+        ChessPieceType::Bishop(_) => 2,
+        ChessPieceType::Rook(_) => 3,
+        ChessPieceType::Queen(_) => 4,
+        ChessPieceType::King(_) => 5,
+    }
+}
+
+impl Board {
+    // Equal for positions that count as the same for the repetition rules.
+    // Different positions can collide, but only with negligible probability.
+    pub fn zobrist_hash(&self, turn: Color) -> u64 {
+        let mut hash = 0;
+        for (row, squares) in self.squares.iter().enumerate() {
+            for (column, square) in squares.iter().enumerate() {
+                if let Some(piece) = square {
+                    let color = usize::from(piece.color == Color::Black);
+                    let index =
+                        (piece_index(&piece.chess_piece) * 2 + color) * 64 + row * 8 + column;
+                    hash ^= KEYS[index];
+                }
+            }
+        }
+
+        let sides = [CastlingSide::KingSide, CastlingSide::QueenSide];
+        for (i, (color, side)) in [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| sides.map(|side| (color, side)))
+            .enumerate()
+        {
+            if self.castling_rights.can_castle(color, side) {
+                hash ^= KEYS[PIECE_KEYS + i];
+            }
+        }
+
+        // The en passant square only matters when the capture can actually be played
+        if let Some(pos) = self.en_passant {
+            if self
+                .get_all_available_moves(turn)
+                .iter()
+                .any(|mv| mv.is_en_passant())
+            {
+                hash ^= KEYS[PIECE_KEYS + CASTLING_KEYS + pos.get_column()];
+            }
+        }
+
+        if turn == Color::Black {
+            hash ^= KEYS[KEY_COUNT - 1];
+        }
+        hash
+    }
+}